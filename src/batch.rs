@@ -0,0 +1,191 @@
+use anyhow::ensure;
+use solana_sdk::{
+    instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+/// Serialized size of a transaction of `ixs` paid by `fee_payer`, with room for every
+/// signature it requires
+pub fn transaction_size(ixs: &[Instruction], fee_payer: &Pubkey) -> usize {
+    let tx = Transaction::new_unsigned(Message::new(ixs, Some(fee_payer)));
+    bincode::serialized_size(&tx).expect("transactions serialize") as usize
+}
+
+/// Whether a transaction of `ixs` paid by `fee_payer` fits in a packet
+pub fn fits(ixs: &[Instruction], fee_payer: &Pubkey) -> bool {
+    transaction_size(ixs, fee_payer) <= PACKET_DATA_SIZE
+}
+
+/// Fill transactions with whole groups of instructions, e.g. everything one account
+/// needs, in order and as many as fit in a packet. Room is left for `prefix`, which is
+/// put in front of every transaction later, such as an advance-nonce instruction.
+pub fn pack(
+    groups: Vec<Vec<Instruction>>,
+    fee_payer: &Pubkey,
+    prefix: &[Instruction],
+) -> anyhow::Result<Vec<Vec<Instruction>>> {
+    let fits = |ixs: &[Instruction]| fits(&[prefix, ixs].concat(), fee_payer);
+
+    let mut batches = Vec::new();
+    let mut batch: Vec<Instruction> = Vec::new();
+    for group in groups.into_iter().filter(|group| !group.is_empty()) {
+        let candidate = [batch.as_slice(), &group].concat();
+        if fits(&candidate) {
+            batch = candidate;
+            continue;
+        }
+        ensure!(
+            fits(&group),
+            "{} instruction(s) for one account do not fit in a transaction",
+            group.len()
+        );
+        batches.push(std::mem::replace(&mut batch, group));
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Burn and close of a fresh token account owned by `owner`, rent sent to `destination`
+    fn burn_and_close(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        destination: &Pubkey,
+    ) -> Vec<Instruction> {
+        let account = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        vec![
+            spl_token_2022::instruction::burn(program_id, &account, &mint, owner, &[], 1).unwrap(),
+            spl_token_2022::instruction::close_account(
+                program_id,
+                &account,
+                destination,
+                owner,
+                &[],
+            )
+            .unwrap(),
+        ]
+    }
+
+    /// Eleven SPL Token accounts and one Token-2022 account, all burned and closed
+    fn twelve_accounts(owner: &Pubkey, destination: &Pubkey) -> Vec<Vec<Instruction>> {
+        (0..12)
+            .map(|i| {
+                let program_id = if i == 0 {
+                    spl_token_2022::id()
+                } else {
+                    spl_token::id()
+                };
+                burn_and_close(&program_id, owner, destination)
+            })
+            .collect()
+    }
+
+    /// Pack `groups` and check that nothing was lost, reordered or split, and that every
+    /// transaction fits. Returns the number of transactions.
+    fn assert_packed(
+        groups: Vec<Vec<Instruction>>,
+        fee_payer: &Pubkey,
+        prefix: &[Instruction],
+    ) -> usize {
+        let batches = pack(groups.clone(), fee_payer, prefix).unwrap();
+        assert_eq!(batches.concat(), groups.concat());
+        for batch in &batches {
+            assert!(fits(&[prefix, batch].concat(), fee_payer));
+        }
+        // Groups stay whole: every batch boundary is also a group boundary
+        let mut boundaries = groups.iter().scan(0, |end, group| {
+            *end += group.len();
+            Some(*end)
+        });
+        let mut end = 0;
+        for batch in &batches {
+            end += batch.len();
+            assert!(boundaries.any(|boundary| boundary == end));
+        }
+        batches.len()
+    }
+
+    #[test]
+    fn separate_fee_payer_is_counted() {
+        let owner = Pubkey::new_unique();
+        let groups = twelve_accounts(&owner, &owner);
+        // Twelve of them just fit when the owner pays
+        assert_eq!(assert_packed(groups.clone(), &owner, &[]), 1);
+        // A fee payer adds a signature and an account key, which pushes them over
+        assert_eq!(assert_packed(groups, &Pubkey::new_unique(), &[]), 2);
+    }
+
+    #[test]
+    fn external_rent_destination_is_counted() {
+        let owner = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        assert_eq!(
+            assert_packed(twelve_accounts(&owner, &owner), &owner, &[]),
+            1
+        );
+        // The treasury is one more account key
+        let groups = twelve_accounts(&owner, &treasury);
+        assert_eq!(assert_packed(groups, &owner, &[]), 2);
+    }
+
+    #[test]
+    fn multisig_signers_are_counted() {
+        let multisig = Pubkey::new_unique();
+        let members = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let members = members.iter().collect::<Vec<_>>();
+        let groups: Vec<_> = (0..12)
+            .map(|_| {
+                let account = Pubkey::new_unique();
+                let mint = Pubkey::new_unique();
+                vec![
+                    spl_token::instruction::burn(
+                        &spl_token::id(),
+                        &account,
+                        &mint,
+                        &multisig,
+                        &members,
+                        1,
+                    )
+                    .unwrap(),
+                    spl_token::instruction::close_account(
+                        &spl_token::id(),
+                        &account,
+                        members[0],
+                        &multisig,
+                        &members,
+                    )
+                    .unwrap(),
+                ]
+            })
+            .collect();
+        // Each instruction lists the members again, and the second one signs too
+        assert!(assert_packed(groups, members[0], &[]) > 1);
+    }
+
+    #[test]
+    fn room_is_left_for_the_nonce_instruction() {
+        let owner = Pubkey::new_unique();
+        let advance = solana_system_interface::instruction::advance_nonce_account(
+            &Pubkey::new_unique(),
+            &owner,
+        );
+        let groups = twelve_accounts(&owner, &owner);
+        assert_eq!(assert_packed(groups.clone(), &owner, &[]), 1);
+        assert_eq!(assert_packed(groups, &owner, &[advance]), 2);
+    }
+
+    #[test]
+    fn oversized_group_is_refused() {
+        let owner = Pubkey::new_unique();
+        let group = (0..40)
+            .flat_map(|_| burn_and_close(&spl_token::id(), &owner, &owner))
+            .collect::<Vec<_>>();
+        assert!(pack(vec![group], &owner, &[]).is_err());
+    }
+}
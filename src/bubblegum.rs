@@ -0,0 +1,199 @@
+use anyhow::{anyhow, ensure};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+};
+
+use crate::das::{AssetProof, CompressedAsset};
+
+/// Metaplex Bubblegum program, which owns the leaves of compressed NFT trees
+pub const PROGRAM_ID: Pubkey = pubkey!("BGUMAPp9X4qw38a2YQvLTkXhPnYVLYdSxCbLHgKtBjXp");
+const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Anchor discriminator of `burn`, the first 8 bytes of sha256("global:burn")
+const BURN_DISCRIMINATOR: [u8; 8] = [116, 110, 29, 56, 107, 219, 42, 93];
+
+/// Size of `ConcurrentMerkleTreeHeader` at the start of a tree account
+const TREE_HEADER_SIZE: usize = 56;
+
+/// Depth of the canopy cached at the end of a concurrent Merkle tree account. The
+/// proof nodes it holds are left out of instructions so larger trees still fit.
+pub fn canopy_depth(tree_data: &[u8]) -> anyhow::Result<usize> {
+    ensure!(
+        tree_data.len() >= TREE_HEADER_SIZE,
+        "not a Merkle tree account"
+    );
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes(tree_data[offset..offset + 4].try_into().expect("4 bytes")) as usize
+    };
+    let max_buffer_size = read_u32(2);
+    let max_depth = read_u32(6);
+
+    // Sequence number, active index and buffer size, the change log buffer, then the
+    // rightmost proof. Change logs and the proof hold `max_depth` nodes plus 40 bytes.
+    let tree_size = 24 + (max_buffer_size + 1) * (40 + 32 * max_depth);
+    let canopy_nodes = tree_data
+        .len()
+        .checked_sub(TREE_HEADER_SIZE + tree_size)
+        .ok_or_else(|| anyhow!("the Merkle tree account is truncated"))?
+        / 32;
+    // A canopy of depth d caches 2^(d+1) - 2 nodes
+    let mut depth = 0;
+    while (1usize << (depth + 2)) - 2 <= canopy_nodes {
+        depth += 1;
+    }
+    Ok(depth)
+}
+
+/// Bubblegum `burn` of `asset`, signed by its owner. `canopy_depth` proof nodes are
+/// read from the tree account instead of being passed. `compression_program` is the
+/// owner of the tree account.
+pub fn burn_instruction(
+    asset: &CompressedAsset,
+    proof: &AssetProof,
+    canopy_depth: usize,
+    compression_program: &Pubkey,
+) -> anyhow::Result<Instruction> {
+    let proof_len = proof.proof.len().saturating_sub(canopy_depth);
+    let tree_authority = Pubkey::find_program_address(&[asset.tree.as_ref()], &PROGRAM_ID).0;
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(tree_authority, false),
+        AccountMeta::new_readonly(asset.owner, true),
+        AccountMeta::new_readonly(asset.delegate, false),
+        AccountMeta::new(asset.tree, false),
+        AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+        AccountMeta::new_readonly(*compression_program, false),
+        AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+    ];
+    accounts.extend(
+        proof.proof[..proof_len]
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node, false)),
+    );
+
+    let index = u32::try_from(asset.leaf_id)
+        .map_err(|_| anyhow!("leaf index {} is out of range", asset.leaf_id))?;
+    let mut data = BURN_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&proof.root);
+    data.extend_from_slice(&asset.data_hash);
+    data.extend_from_slice(&asset.creator_hash);
+    data.extend_from_slice(&asset.leaf_id.to_le_bytes());
+    data.extend_from_slice(&index.to_le_bytes());
+    Ok(Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tree account of `max_depth` and `max_buffer_size` with a canopy of `canopy_depth`
+    fn tree_data(max_depth: u32, max_buffer_size: u32, canopy_depth: u32) -> Vec<u8> {
+        let mut data = vec![1, 0]; // account type, header version
+        data.extend_from_slice(&max_buffer_size.to_le_bytes());
+        data.extend_from_slice(&max_depth.to_le_bytes());
+        data.resize(TREE_HEADER_SIZE, 0);
+        let tree_size = 24 + (max_buffer_size as usize + 1) * (40 + 32 * max_depth as usize);
+        let canopy_nodes = (1usize << (canopy_depth + 1)) - 2;
+        data.resize(TREE_HEADER_SIZE + tree_size + canopy_nodes * 32, 0);
+        data
+    }
+
+    #[test]
+    fn canopy_depth_of_tree_accounts() {
+        // A depth 14, buffer 64 tree without canopy is the well-known 31,800 bytes
+        assert_eq!(tree_data(14, 64, 0).len(), 31_800);
+        assert_eq!(canopy_depth(&tree_data(14, 64, 0)).unwrap(), 0);
+        for depth in [1, 5, 10, 14] {
+            assert_eq!(
+                canopy_depth(&tree_data(14, 64, depth)).unwrap(),
+                depth as usize
+            );
+            assert_eq!(
+                canopy_depth(&tree_data(20, 256, depth)).unwrap(),
+                depth as usize
+            );
+        }
+    }
+
+    #[test]
+    fn canopy_depth_rejects_other_accounts() {
+        assert!(canopy_depth(&[0; 10]).is_err());
+        let mut truncated = tree_data(14, 64, 0);
+        truncated.pop();
+        assert!(canopy_depth(&truncated).is_err());
+    }
+
+    #[test]
+    fn burn_instruction_layout() {
+        let asset = CompressedAsset {
+            id: Pubkey::new_unique(),
+            name: String::new(),
+            tree: Pubkey::new_unique(),
+            leaf_id: 0x0102_0304,
+            data_hash: [2; 32],
+            creator_hash: [3; 32],
+            owner: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+        };
+        let proof = AssetProof {
+            root: [1; 32],
+            proof: (0..14).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let compression_program = Pubkey::new_unique();
+        let ix = burn_instruction(&asset, &proof, 10, &compression_program).unwrap();
+
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        let mut data = BURN_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[1; 32]);
+        data.extend_from_slice(&[2; 32]);
+        data.extend_from_slice(&[3; 32]);
+        data.extend_from_slice(&0x0102_0304u64.to_le_bytes());
+        data.extend_from_slice(&0x0102_0304u32.to_le_bytes());
+        assert_eq!(ix.data, data);
+
+        let tree_authority = Pubkey::find_program_address(&[asset.tree.as_ref()], &PROGRAM_ID).0;
+        let expected = [
+            AccountMeta::new_readonly(tree_authority, false),
+            AccountMeta::new_readonly(asset.owner, true),
+            AccountMeta::new_readonly(asset.delegate, false),
+            AccountMeta::new(asset.tree, false),
+            AccountMeta::new_readonly(NOOP_PROGRAM_ID, false),
+            AccountMeta::new_readonly(compression_program, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ];
+        assert_eq!(ix.accounts[..7], expected);
+        // Only the proof nodes below the canopy are passed, from the leaf upwards
+        let nodes: Vec<Pubkey> = ix.accounts[7..].iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(nodes, proof.proof[..4]);
+        assert!(
+            ix.accounts[7..]
+                .iter()
+                .all(|meta| !meta.is_writable && !meta.is_signer)
+        );
+    }
+
+    #[test]
+    fn burn_instruction_rejects_large_leaf_indexes() {
+        let asset = CompressedAsset {
+            id: Pubkey::new_unique(),
+            name: String::new(),
+            tree: Pubkey::new_unique(),
+            leaf_id: u64::from(u32::MAX) + 1,
+            data_hash: [0; 32],
+            creator_hash: [0; 32],
+            owner: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+        };
+        let proof = AssetProof {
+            root: [0; 32],
+            proof: Vec::new(),
+        };
+        assert!(burn_instruction(&asset, &proof, 0, &Pubkey::new_unique()).is_err());
+    }
+}
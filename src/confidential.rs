@@ -0,0 +1,102 @@
+use anyhow::{anyhow, ensure};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token_2022::extension::confidential_transfer::{
+    ConfidentialTransferAccount, EncryptedBalance,
+    account_info::ApplyPendingBalanceAccountInfo,
+    instruction::{ZeroCiphertextProofData, apply_pending_balance, empty_account},
+};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::solana_zk_sdk::encryption::{
+    auth_encryption::AeKey, elgamal::ElGamalKeypair, pod::elgamal::PodElGamalPubkey,
+};
+use spl_token_confidential_transfer_ciphertext_arithmetic::add_with_lo_hi;
+use spl_token_confidential_transfer_proof_extraction::instruction::{ProofData, ProofLocation};
+
+use std::num::NonZeroI8;
+
+/// Instructions that clear the confidential balances of the token account `address` so
+/// it can be closed: apply its pending balance, then prove the available balance is zero.
+///
+/// The confidential keys are derived from `owner` with the account address as seed, as
+/// `spl-token configure-confidential-transfer-account` does. Balances that are not zero
+/// must be withdrawn to the public balance first. The proof is verified in the same
+/// transaction, right after the `EmptyAccount` instruction.
+pub fn empty_instructions(
+    program_id: &Pubkey,
+    address: &Pubkey,
+    data: &[u8],
+    owner: &Keypair,
+) -> anyhow::Result<Vec<Instruction>> {
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(data)?;
+    let Ok(extension) = state.get_extension::<ConfidentialTransferAccount>() else {
+        return Ok(Vec::new());
+    };
+    if extension.closable().is_ok() {
+        return Ok(Vec::new());
+    }
+
+    let seed = address.to_bytes();
+    let elgamal_keypair = ElGamalKeypair::new_from_signer(owner, &seed)
+        .map_err(|e| anyhow!("failed to derive the ElGamal key: {e}"))?;
+    let aes_key = AeKey::new_from_signer(owner, &seed)
+        .map_err(|e| anyhow!("failed to derive the AES key: {e}"))?;
+    ensure!(
+        PodElGamalPubkey::from(*elgamal_keypair.pubkey()) == extension.elgamal_pubkey,
+        "its confidential keys were not derived from {}",
+        owner.pubkey()
+    );
+
+    // Decrypt available + pending, which is also the balance after applying
+    let pending = ApplyPendingBalanceAccountInfo::new(extension);
+    let new_decryptable_balance = pending
+        .new_decryptable_available_balance(elgamal_keypair.secret(), &aes_key)
+        .map_err(|e| anyhow!("failed to decrypt the confidential balance: {e}"))?;
+    let balance = aes_key
+        .decrypt(&new_decryptable_balance)
+        .ok_or_else(|| anyhow!("failed to decrypt the confidential balance"))?;
+    ensure!(
+        balance == 0,
+        "it holds {balance} confidential base unit(s), withdraw them to the public balance first"
+    );
+
+    let mut ixs = Vec::new();
+    let mut available_balance = extension.available_balance;
+    if pending.pending_balance_credit_counter() > 0 {
+        ixs.push(apply_pending_balance(
+            program_id,
+            address,
+            pending.pending_balance_credit_counter(),
+            &new_decryptable_balance.into(),
+            &owner.pubkey(),
+            &[],
+        )?);
+        // Mirror what the program computes so the proof matches the applied balance
+        available_balance = add_with_lo_hi(
+            &available_balance,
+            &extension.pending_balance_lo,
+            &extension.pending_balance_hi,
+        )
+        .ok_or_else(|| anyhow!("failed to add the pending balance"))?;
+    }
+
+    if available_balance != EncryptedBalance::default() {
+        let proof = ZeroCiphertextProofData::new(&elgamal_keypair, &available_balance.try_into()?)
+            .map_err(|e| anyhow!("failed to generate the zero balance proof: {e}"))?;
+        ixs.extend(empty_account(
+            program_id,
+            address,
+            &owner.pubkey(),
+            &[],
+            ProofLocation::InstructionOffset(
+                NonZeroI8::new(1).expect("non-zero"),
+                ProofData::InstructionData(&proof),
+            ),
+        )?);
+    }
+
+    Ok(ixs)
+}
@@ -0,0 +1,336 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::Deserialize;
+use serde_json::json;
+use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::pubkey::Pubkey;
+
+use std::str::FromStr;
+
+/// Assets requested per `getAssetsByOwner` page, the maximum indexers accept
+const PAGE_LIMIT: usize = 1000;
+
+/// Client of an indexer implementing the Digital Asset Standard (DAS) JSON-RPC API,
+/// which is the only way to list compressed NFTs
+pub struct DasClient {
+    rpc_client: RpcClient,
+}
+
+/// A compressed NFT: a leaf of a Bubblegum Merkle tree
+#[derive(Debug, Clone)]
+pub struct CompressedAsset {
+    pub id: Pubkey,
+    pub name: String,
+    pub tree: Pubkey,
+    /// Leaf index in the tree, also used as the leaf nonce
+    pub leaf_id: u64,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub owner: Pubkey,
+    /// Leaf delegate, the owner when none is set
+    pub delegate: Pubkey,
+}
+
+/// Merkle proof of a leaf, from the tree's current root
+pub struct AssetProof {
+    pub root: [u8; 32],
+    /// Sibling nodes from the leaf upwards
+    pub proof: Vec<Pubkey>,
+}
+
+#[derive(Deserialize)]
+struct AssetPage {
+    items: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    id: String,
+    #[serde(default)]
+    burnt: bool,
+    content: Option<Content>,
+    compression: Option<Compression>,
+    ownership: Ownership,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    metadata: Option<Metadata>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Compression {
+    compressed: bool,
+    tree: String,
+    leaf_id: u64,
+    data_hash: String,
+    creator_hash: String,
+}
+
+#[derive(Deserialize)]
+struct Ownership {
+    owner: String,
+    delegate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Proof {
+    root: String,
+    proof: Vec<String>,
+}
+
+impl AssetPage {
+    /// The compressed assets of the page that are not burnt yet
+    fn compressed_assets(self) -> Result<Vec<CompressedAsset>> {
+        let mut assets = Vec::new();
+        for asset in self.items {
+            if asset.burnt {
+                continue;
+            }
+            let Some(compression) = asset.compression.filter(|c| c.compressed) else {
+                continue;
+            };
+            let owner = parse_pubkey(&asset.ownership.owner)?;
+            assets.push(CompressedAsset {
+                id: parse_pubkey(&asset.id)?,
+                name: asset
+                    .content
+                    .and_then(|content| content.metadata)
+                    .and_then(|metadata| metadata.name)
+                    .unwrap_or_default(),
+                tree: parse_pubkey(&compression.tree)?,
+                leaf_id: compression.leaf_id,
+                data_hash: parse_hash(&compression.data_hash)?,
+                creator_hash: parse_hash(&compression.creator_hash)?,
+                owner,
+                delegate: match asset.ownership.delegate {
+                    Some(delegate) => parse_pubkey(&delegate)?,
+                    None => owner,
+                },
+            });
+        }
+        Ok(assets)
+    }
+}
+
+impl Proof {
+    fn decode(self) -> Result<AssetProof> {
+        Ok(AssetProof {
+            root: parse_hash(&self.root)?,
+            proof: self
+                .proof
+                .iter()
+                .map(|node| parse_pubkey(node))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl DasClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            rpc_client: RpcClient::new(url),
+        }
+    }
+
+    /// Compressed NFTs held by `owner` that are not burnt yet
+    pub fn compressed_assets(&self, owner: &Pubkey) -> Result<Vec<CompressedAsset>> {
+        let mut assets = Vec::new();
+        for page in 1.. {
+            let response: AssetPage = self
+                .rpc_client
+                .send(
+                    RpcRequest::Custom {
+                        method: "getAssetsByOwner",
+                    },
+                    json!({
+                        "ownerAddress": owner.to_string(),
+                        "page": page,
+                        "limit": PAGE_LIMIT,
+                    }),
+                )
+                .map_err(|e| eyre!("Failed to list assets from the DAS indexer: {}", e))?;
+            let count = response.items.len();
+            assets.extend(response.compressed_assets()?);
+            if count < PAGE_LIMIT {
+                break;
+            }
+        }
+        Ok(assets)
+    }
+
+    /// Current Merkle proof of the compressed asset `id`
+    pub fn asset_proof(&self, id: &Pubkey) -> Result<AssetProof> {
+        let proof: Proof = self
+            .rpc_client
+            .send(
+                RpcRequest::Custom {
+                    method: "getAssetProof",
+                },
+                json!({ "id": id.to_string() }),
+            )
+            .map_err(|e| eyre!("Failed to fetch the proof of {}: {}", id, e))?;
+        proof.decode()
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value)
+        .map_err(|e| eyre!("Invalid address {} from the DAS indexer: {}", value, e))
+}
+
+/// 32-byte hashes are base58 encoded, like addresses
+fn parse_hash(value: &str) -> Result<[u8; 32]> {
+    parse_pubkey(value).map(|pubkey| pubkey.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `getAssetsByOwner` result trimmed to the fields read, with one asset of each kind
+    const PAGE: &str = r#"{
+        "total": 4,
+        "limit": 1000,
+        "page": 1,
+        "items": [
+            {
+                "interface": "V1_NFT",
+                "id": "6Z9Gb5k6ZfxhTumRzv2ydQJ2Xb4NBe1gbDKXRg7FXy3A",
+                "burnt": false,
+                "content": { "metadata": { "name": "Leaf #7", "symbol": "LEAF" } },
+                "compression": {
+                    "compressed": true,
+                    "tree": "2p8jAxGbm3NbdiTXyU8Hn9yGQH5Xz8hbf8yfdD9QfPqE",
+                    "leaf_id": 7,
+                    "seq": 12,
+                    "data_hash": "So11111111111111111111111111111111111111112",
+                    "creator_hash": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                },
+                "ownership": {
+                    "owner": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM",
+                    "delegate": "BGUMAPp9X4qw38a2YQvLTkXhPnYVLYdSxCbLHgKtBjXp",
+                    "delegated": true
+                }
+            },
+            {
+                "interface": "V1_NFT",
+                "id": "HPx8gHkmpS5nZg2dBw8HxKSjtR3Ev3TLDSBJXxPHhQ8r",
+                "compression": {
+                    "compressed": true,
+                    "tree": "2p8jAxGbm3NbdiTXyU8Hn9yGQH5Xz8hbf8yfdD9QfPqE",
+                    "leaf_id": 8,
+                    "data_hash": "So11111111111111111111111111111111111111112",
+                    "creator_hash": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                },
+                "ownership": {
+                    "owner": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM",
+                    "delegate": null
+                }
+            },
+            {
+                "interface": "V1_NFT",
+                "id": "4GqQ9fKNZL8q5E4c4TPwQq1vR4xdYwTDJS4QdUXgGcZg",
+                "burnt": true,
+                "compression": {
+                    "compressed": true,
+                    "tree": "2p8jAxGbm3NbdiTXyU8Hn9yGQH5Xz8hbf8yfdD9QfPqE",
+                    "leaf_id": 9,
+                    "data_hash": "So11111111111111111111111111111111111111112",
+                    "creator_hash": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                },
+                "ownership": { "owner": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM" }
+            },
+            {
+                "interface": "V1_NFT",
+                "id": "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU",
+                "content": { "metadata": { "name": "Uncompressed" } },
+                "compression": {
+                    "compressed": false,
+                    "tree": "",
+                    "leaf_id": 0,
+                    "data_hash": "",
+                    "creator_hash": ""
+                },
+                "ownership": { "owner": "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM" }
+            }
+        ]
+    }"#;
+
+    fn pubkey(value: &str) -> Pubkey {
+        Pubkey::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn parses_compressed_assets() {
+        let page: AssetPage = serde_json::from_str(PAGE).unwrap();
+        let assets = page.compressed_assets().unwrap();
+        // The burnt and the uncompressed assets are left out
+        assert_eq!(assets.len(), 2);
+
+        let owner = pubkey("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM");
+        let delegated = &assets[0];
+        assert_eq!(
+            delegated.id,
+            pubkey("6Z9Gb5k6ZfxhTumRzv2ydQJ2Xb4NBe1gbDKXRg7FXy3A")
+        );
+        assert_eq!(delegated.name, "Leaf #7");
+        assert_eq!(
+            delegated.tree,
+            pubkey("2p8jAxGbm3NbdiTXyU8Hn9yGQH5Xz8hbf8yfdD9QfPqE")
+        );
+        assert_eq!(delegated.leaf_id, 7);
+        assert_eq!(
+            delegated.data_hash,
+            pubkey("So11111111111111111111111111111111111111112").to_bytes()
+        );
+        assert_eq!(delegated.creator_hash, spl_token::id().to_bytes());
+        assert_eq!(delegated.owner, owner);
+        assert_eq!(delegated.delegate, crate::bubblegum::PROGRAM_ID);
+
+        // Without a delegate the owner stands in, and a missing name is empty
+        let plain = &assets[1];
+        assert_eq!(plain.name, "");
+        assert_eq!(plain.leaf_id, 8);
+        assert_eq!(plain.delegate, owner);
+    }
+
+    #[test]
+    fn rejects_invalid_hashes() {
+        let page = PAGE.replacen(
+            "So11111111111111111111111111111111111111112",
+            "not-base58",
+            1,
+        );
+        let page: AssetPage = serde_json::from_str(&page).unwrap();
+        assert!(page.compressed_assets().is_err());
+    }
+
+    #[test]
+    fn parses_proofs() {
+        let proof: Proof = serde_json::from_str(
+            r#"{
+                "root": "So11111111111111111111111111111111111111112",
+                "proof": [
+                    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                    "BGUMAPp9X4qw38a2YQvLTkXhPnYVLYdSxCbLHgKtBjXp"
+                ],
+                "node_index": 16391,
+                "leaf": "6Z9Gb5k6ZfxhTumRzv2ydQJ2Xb4NBe1gbDKXRg7FXy3A",
+                "tree_id": "2p8jAxGbm3NbdiTXyU8Hn9yGQH5Xz8hbf8yfdD9QfPqE"
+            }"#,
+        )
+        .unwrap();
+        let proof = proof.decode().unwrap();
+        assert_eq!(
+            proof.root,
+            pubkey("So11111111111111111111111111111111111111112").to_bytes()
+        );
+        assert_eq!(proof.proof, [spl_token::id(), crate::bubblegum::PROGRAM_ID]);
+    }
+}
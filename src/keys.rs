@@ -0,0 +1,280 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use solana_derivation_path::DerivationPath;
+use solana_keypair::seed_derivable::keypair_from_seed_and_derivation_path;
+use solana_sdk::signature::Keypair;
+
+use std::io::{BufRead, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+use zeroize::Zeroizing;
+
+use crate::keystore;
+use crate::secret::{KEYPAIR_LEN, KeypairBytes, SecretString, keypair_bytes};
+
+/// Where the signing key is read from
+pub enum KeySource {
+    /// Solana CLI keypair file
+    File(PathBuf),
+    /// Secret passed directly on the command line, moved out of the parsed arguments
+    Inline(SecretString),
+    /// Secret stored in the named environment variable
+    Env(String),
+    /// Hidden interactive prompt, or stdin when it is piped
+    Prompt,
+    /// BIP39 seed phrase read from a hidden prompt, or stdin when it is piped
+    SeedPhrase(SeedPhraseOptions),
+    /// Named entry of the password-encrypted keystore
+    Keystore(String),
+}
+
+/// How to turn a BIP39 seed phrase into keypairs
+pub struct SeedPhraseOptions {
+    /// Path used when not scanning
+    pub derivation_path: DerivationPath,
+    /// Also ask for a BIP39 passphrase
+    pub passphrase: bool,
+    /// Number of `m/44'/501'/N'/0'` accounts to offer, 0 to use `derivation_path` directly
+    pub scan: u32,
+}
+
+/// Encodings accepted for a raw secret key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretFormat {
+    Base58,
+    JsonArray,
+    Hex,
+}
+
+impl KeySource {
+    /// Resolve the source into a keypair. Must run before the TUI takes over the terminal.
+    pub fn load(self) -> Result<Keypair> {
+        match self {
+            KeySource::File(path) => read_keypair(&path),
+            KeySource::Inline(secret) => parse_secret(&secret),
+            KeySource::Env(name) => {
+                let secret =
+                    SecretString::new(std::env::var(&name).map_err(|e| {
+                        eyre!("Failed to read environment variable {}: {}", name, e)
+                    })?);
+                parse_secret(&secret)
+            }
+            KeySource::Prompt => parse_secret(&read_secret_input(
+                "Private key (base58, JSON array or hex): ",
+            )?),
+            KeySource::SeedPhrase(options) => {
+                let seed = read_seed(options.passphrase)?;
+                derive_keypair(&seed[..], &options.derivation_path)
+            }
+            KeySource::Keystore(name) => keystore::unlock(&name),
+        }
+    }
+
+    /// Resolve the source into every keypair the user may pick from, labelled by origin.
+    /// Only a scanning seed phrase yields more than one candidate.
+    pub fn load_candidates(self) -> Result<Vec<(String, Keypair)>> {
+        match self {
+            KeySource::SeedPhrase(options) if options.scan > 0 => {
+                let seed = read_seed(options.passphrase)?;
+                (0..options.scan)
+                    .map(|account| {
+                        let path = DerivationPath::new_bip44(Some(account), Some(0));
+                        Ok((format!("{:?}", path), derive_keypair(&seed[..], &path)?))
+                    })
+                    .collect()
+            }
+            source => Ok(vec![(String::new(), source.load()?)]),
+        }
+    }
+}
+
+/// Parse a `--derivation-path` argument such as `m/44'/501'/0'/0'`
+pub fn parse_derivation_path(path: &str) -> std::result::Result<DerivationPath, String> {
+    DerivationPath::from_absolute_path_str(path).map_err(|e| e.to_string())
+}
+
+/// Read and validate a seed phrase, then stretch it into a 64-byte BIP39 seed
+fn read_seed(passphrase: bool) -> Result<Zeroizing<[u8; 64]>> {
+    let phrase = read_secret_input("Seed phrase: ")?;
+    let mnemonic = bip39::Mnemonic::parse_normalized(phrase.trim())
+        .map_err(|e| eyre!("Invalid seed phrase: {}", e))?;
+    let passphrase = if passphrase {
+        read_secret_input("BIP39 passphrase: ")?
+    } else {
+        SecretString::default()
+    };
+
+    Ok(Zeroizing::new(
+        mnemonic.to_seed(passphrase.trim_end_matches(['\r', '\n'])),
+    ))
+}
+
+fn derive_keypair(seed: &[u8], path: &DerivationPath) -> Result<Keypair> {
+    keypair_from_seed_and_derivation_path(seed, Some(path.clone()))
+        .map_err(|e| eyre!("Failed to derive keypair at {:?}: {}", path, e))
+}
+
+/// Read a keypair file in the format written by `solana-keygen`
+pub fn read_keypair(path: &Path) -> Result<Keypair> {
+    let read = || -> std::io::Result<SecretString> {
+        let mut file = std::fs::File::open(path)?;
+        // Size the buffer up front so it is never reallocated around the secret
+        let len = file.metadata()?.len() as usize;
+        let mut contents = SecretString::new(String::with_capacity(len + 1));
+        file.read_to_string(&mut contents)?;
+        Ok(contents)
+    };
+    let contents =
+        read().map_err(|e| eyre!("Failed to read keypair file {}: {}", path.display(), e))?;
+    let bytes = decode_secret(&contents, SecretFormat::JsonArray)
+        .map_err(|e| eyre!("Failed to parse keypair file {}: {}", path.display(), e))?;
+
+    keypair_from_bytes(&bytes[..])
+}
+
+/// Parse a secret key, detecting whether it is base58, a JSON byte array or hex
+pub fn parse_secret(input: &str) -> Result<Keypair> {
+    let format = detect_format(input);
+    let bytes = decode_secret(input, format)
+        .map_err(|e| eyre!("Failed to decode private key as {:?}: {}", format, e))?;
+
+    keypair_from_bytes(&bytes[..])
+}
+
+/// Guess the encoding of a secret key from its shape
+pub fn detect_format(input: &str) -> SecretFormat {
+    let input = input.trim();
+    let hex_digits = input.strip_prefix("0x").unwrap_or(input);
+
+    if input.starts_with('[') {
+        SecretFormat::JsonArray
+    } else if hex_digits.len() == 128 && hex_digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        // 64 bytes in hex; a base58 encoding of 64 bytes is at most 88 characters
+        SecretFormat::Hex
+    } else {
+        SecretFormat::Base58
+    }
+}
+
+/// Decode a secret into a fixed, self-wiping buffer of exactly [`KEYPAIR_LEN`] bytes
+pub fn decode_secret(input: &str, format: SecretFormat) -> anyhow::Result<KeypairBytes> {
+    let input = input.trim();
+    let mut bytes = keypair_bytes();
+    match format {
+        SecretFormat::Base58 => {
+            let len = bs58::decode(input).onto(&mut bytes[..])?;
+            anyhow::ensure!(
+                len == KEYPAIR_LEN,
+                "expected {KEYPAIR_LEN} bytes, got {len}"
+            );
+        }
+        SecretFormat::JsonArray => {
+            let inner = input
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+                .ok_or_else(|| anyhow::anyhow!("expected a JSON array of bytes"))?;
+            let mut len = 0;
+            for value in inner.split(',') {
+                anyhow::ensure!(len < KEYPAIR_LEN, "expected {KEYPAIR_LEN} bytes, got more");
+                bytes[len] = value.trim().parse()?;
+                len += 1;
+            }
+            anyhow::ensure!(
+                len == KEYPAIR_LEN,
+                "expected {KEYPAIR_LEN} bytes, got {len}"
+            );
+        }
+        SecretFormat::Hex => {
+            hex::decode_to_slice(input.strip_prefix("0x").unwrap_or(input), &mut bytes[..])?
+        }
+    }
+    Ok(bytes)
+}
+
+fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair> {
+    Keypair::try_from(bytes).map_err(|e| eyre!("Failed to create keypair: {}", e))
+}
+
+/// Read a secret from a no-echo prompt, or the next line of stdin when piped
+pub fn read_secret_input(prompt: &str) -> Result<SecretString> {
+    if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt)
+            .map(SecretString::new)
+            .map_err(|e| eyre!("Failed to read secret: {}", e))
+    } else {
+        // Preallocated so that typical secrets never trigger a reallocation
+        let mut line = SecretString::new(String::with_capacity(1024));
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| eyre!("Failed to read secret from stdin: {}", e))?;
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    fn json_array(bytes: &[u8]) -> String {
+        format!(
+            "[{}]",
+            bytes
+                .iter()
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+
+    #[test]
+    fn decodes_every_format() {
+        let keypair = Keypair::new();
+        let bytes = keypair.to_bytes();
+        let inputs = [
+            (bs58::encode(bytes).into_string(), SecretFormat::Base58),
+            (json_array(&bytes), SecretFormat::JsonArray),
+            (hex::encode(bytes), SecretFormat::Hex),
+            (format!("0x{}", hex::encode(bytes)), SecretFormat::Hex),
+        ];
+        for (input, format) in inputs {
+            assert_eq!(detect_format(&input), format);
+            // Surrounding whitespace, e.g. a trailing newline from stdin, is ignored
+            let decoded = decode_secret(&format!(" {input}\n"), format).unwrap();
+            assert_eq!(*decoded, bytes);
+            assert_eq!(parse_secret(&input).unwrap().pubkey(), keypair.pubkey());
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_lengths() {
+        for len in [32, 63, 65] {
+            let bytes = vec![1u8; len];
+            assert!(
+                decode_secret(&bs58::encode(&bytes).into_string(), SecretFormat::Base58).is_err()
+            );
+            assert!(decode_secret(&json_array(&bytes), SecretFormat::JsonArray).is_err());
+            assert!(decode_secret(&hex::encode(&bytes), SecretFormat::Hex).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(decode_secret("not base58 0OIl", SecretFormat::Base58).is_err());
+        assert!(decode_secret("[1, 2, x]", SecretFormat::JsonArray).is_err());
+        assert!(decode_secret("[1, 2, 256]", SecretFormat::JsonArray).is_err());
+        assert!(decode_secret("1, 2, 3", SecretFormat::JsonArray).is_err());
+        assert!(decode_secret(&"zz".repeat(64), SecretFormat::Hex).is_err());
+    }
+
+    #[test]
+    fn reads_solana_cli_keypair_files() {
+        let keypair = Keypair::new();
+        let path = std::env::temp_dir().join(format!("{}.json", keypair.pubkey()));
+        std::fs::write(&path, json_array(&keypair.to_bytes())).unwrap();
+        let read = read_keypair(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap().pubkey(), keypair.pubkey());
+    }
+}
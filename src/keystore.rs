@@ -0,0 +1,292 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+
+use std::path::PathBuf;
+
+use zeroize::Zeroizing;
+
+use crate::keys::read_secret_input;
+
+const KEYSTORE_VERSION: u8 = 1;
+/// Argon2id cost: 64 MiB of memory, 3 passes, single lane
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// On-disk format of a stored signer. The public key stays in clear so entries can be
+/// listed without a password, and is bound to the ciphertext as associated data.
+#[derive(Serialize, Deserialize)]
+struct KeystoreEntry {
+    version: u8,
+    pubkey: String,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    salt: String,
+}
+
+/// Directory holding the encrypted signers, e.g. `~/.config/solana-burn-cli/keystore`
+pub fn keystore_dir() -> Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("solana-burn-cli").join("keystore"))
+        .ok_or_else(|| eyre!("Failed to locate the user config directory"))
+}
+
+fn entry_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(eyre!(
+            "Invalid keystore name {:?}: use letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    Ok(keystore_dir()?.join(format!("{}.json", name)))
+}
+
+/// Encrypt `keypair` with a freshly prompted password and store it under `name`
+pub fn import(name: &str, keypair: &Keypair) -> Result<PathBuf> {
+    let path = entry_path(name)?;
+    if path.exists() {
+        return Err(eyre!("Keystore entry {:?} already exists", name));
+    }
+
+    let password = read_secret_input("New keystore password: ")?;
+    let confirmation = read_secret_input("Confirm password: ")?;
+    if password != confirmation {
+        return Err(eyre!("Passwords do not match"));
+    }
+    if password.trim_end_matches(['\r', '\n']).is_empty() {
+        return Err(eyre!("Keystore password must not be empty"));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = KdfParams {
+        algorithm: "argon2id".to_string(),
+        memory_kib: KDF_MEMORY_KIB,
+        iterations: KDF_ITERATIONS,
+        parallelism: KDF_PARALLELISM,
+        salt: hex::encode(salt),
+    };
+    let entry = seal(keypair, &password, kdf)?;
+
+    std::fs::create_dir_all(keystore_dir()?)?;
+    write_private_file(&path, serde_json::to_string_pretty(&entry)?.as_bytes())?;
+    Ok(path)
+}
+
+/// Encrypt `keypair` under a key derived from `password` with `kdf`
+fn seal(keypair: &Keypair, password: &str, kdf: KdfParams) -> Result<KeystoreEntry> {
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+
+    let pubkey = keypair.pubkey().to_string();
+    let cipher = cipher_for(password, &kdf)?;
+    let secret = Zeroizing::new(keypair.to_bytes());
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &secret[..],
+                aad: pubkey.as_bytes(),
+            },
+        )
+        .map_err(|_| eyre!("Failed to encrypt keypair"))?;
+
+    Ok(KeystoreEntry {
+        version: KEYSTORE_VERSION,
+        pubkey,
+        kdf,
+        cipher: "xchacha20poly1305".to_string(),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+/// Prompt for the password of entry `name` and decrypt its keypair
+pub fn unlock(name: &str) -> Result<Keypair> {
+    let entry = read_entry(name)?;
+    if entry.version != KEYSTORE_VERSION {
+        return Err(eyre!(
+            "Unsupported keystore version {} in entry {:?}",
+            entry.version,
+            name
+        ));
+    }
+
+    let password = read_secret_input(&format!("Password for keystore entry {}: ", name))?;
+    open(&entry, &password)
+        .map_err(|_| eyre!("Wrong password or corrupted keystore entry {:?}", name))
+}
+
+/// Decrypt the keypair of `entry`, checking it against the public key stored in clear
+fn open(entry: &KeystoreEntry, password: &str) -> Result<Keypair> {
+    let cipher = cipher_for(password, &entry.kdf)?;
+    let nonce = hex::decode(&entry.nonce)?;
+    let ciphertext = hex::decode(&entry.ciphertext)?;
+    let plaintext = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: entry.pubkey.as_bytes(),
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| eyre!("Failed to decrypt keypair"))?;
+
+    Keypair::try_from(&plaintext[..]).map_err(|e| eyre!("Failed to create keypair: {}", e))
+}
+
+/// Names and public keys of all stored signers, sorted by name
+pub fn list() -> Result<Vec<(String, String)>> {
+    let dir = keystore_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for file in std::fs::read_dir(&dir)? {
+        let path = file?.path();
+        if path.extension().is_some_and(|ext| ext == "json")
+            && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+        {
+            entries.push((name.to_string(), read_entry(name)?.pubkey));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Delete the stored signer `name`
+pub fn remove(name: &str) -> Result<()> {
+    let path = entry_path(name)?;
+    std::fs::remove_file(&path)
+        .map_err(|e| eyre!("Failed to remove keystore entry {:?}: {}", name, e))
+}
+
+fn read_entry(name: &str) -> Result<KeystoreEntry> {
+    let path = entry_path(name)?;
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| eyre!("Failed to read keystore entry {:?}: {}", name, e))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| eyre!("Failed to parse keystore entry {:?}: {}", name, e))
+}
+
+/// Derive the AEAD key from the password with Argon2id
+fn cipher_for(password: &str, kdf: &KdfParams) -> Result<XChaCha20Poly1305> {
+    if kdf.algorithm != "argon2id" {
+        return Err(eyre!("Unsupported key derivation {:?}", kdf.algorithm));
+    }
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| eyre!("Invalid key derivation parameters: {}", e))?;
+    let salt = hex::decode(&kdf.salt)?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(
+            password.trim_end_matches(['\r', '\n']).as_bytes(),
+            &salt,
+            &mut key[..],
+        )
+        .map_err(|e| eyre!("Failed to derive keystore key: {}", e))?;
+
+    XChaCha20Poly1305::new_from_slice(&key[..]).map_err(|e| eyre!("Invalid keystore key: {}", e))
+}
+
+/// Create `path` readable by the current user only
+fn write_private_file(path: &std::path::Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options.open(path)?.write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so the tests do not spend 64 MiB and seconds per derivation
+    fn test_kdf() -> KdfParams {
+        KdfParams {
+            algorithm: "argon2id".to_string(),
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+            salt: hex::encode([7u8; SALT_LEN]),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let keypair = Keypair::new();
+        let entry = seal(&keypair, "correct horse", test_kdf()).unwrap();
+        assert_eq!(entry.pubkey, keypair.pubkey().to_string());
+
+        // The entry survives serialization as written to disk
+        let entry: KeystoreEntry =
+            serde_json::from_str(&serde_json::to_string(&entry).unwrap()).unwrap();
+        let unlocked = open(&entry, "correct horse").unwrap();
+        assert_eq!(unlocked.to_bytes(), keypair.to_bytes());
+    }
+
+    #[test]
+    fn trailing_newline_is_ignored() {
+        let keypair = Keypair::new();
+        let entry = seal(&keypair, "correct horse\n", test_kdf()).unwrap();
+        assert!(open(&entry, "correct horse").is_ok());
+    }
+
+    #[test]
+    fn wrong_password_is_rejected() {
+        let entry = seal(&Keypair::new(), "correct horse", test_kdf()).unwrap();
+        assert!(open(&entry, "battery staple").is_err());
+    }
+
+    #[test]
+    fn tampered_pubkey_is_rejected() {
+        let mut entry = seal(&Keypair::new(), "correct horse", test_kdf()).unwrap();
+        entry.pubkey = Keypair::new().pubkey().to_string();
+        assert!(open(&entry, "correct horse").is_err());
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let mut entry = seal(&Keypair::new(), "correct horse", test_kdf()).unwrap();
+        let mut ciphertext = hex::decode(&entry.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        entry.ciphertext = hex::encode(ciphertext);
+        assert!(open(&entry, "correct horse").is_err());
+    }
+
+    #[test]
+    fn unknown_kdf_is_rejected() {
+        let mut kdf = test_kdf();
+        kdf.algorithm = "scrypt".to_string();
+        assert!(cipher_for("correct horse", &kdf).is_err());
+    }
+}
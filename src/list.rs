@@ -0,0 +1,150 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+};
+
+/// How a row type is shown in a [`SelectableList`] table
+pub trait ListRow {
+    /// Column titles and widths, after the selection column
+    fn columns() -> Vec<(&'static str, Constraint)>;
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Rows with a selection flag each and a highlighted row, for the tabs besides the
+/// token accounts
+pub struct SelectableList<T> {
+    pub items: Vec<(T, bool)>,
+    pub state: TableState,
+}
+
+impl<T> Default for SelectableList<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            state: TableState::default(),
+        }
+    }
+}
+
+impl<T> SelectableList<T> {
+    /// Replace the rows, none selected, highlighting the first
+    pub fn set_items(&mut self, items: Vec<T>) {
+        self.items = items.into_iter().map(|item| (item, false)).collect();
+        self.state
+            .select(if self.items.is_empty() { None } else { Some(0) });
+    }
+
+    pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(i) if i + 1 < self.items.len() => i + 1,
+            _ => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let i = match self.state.selected() {
+            Some(0) | None => self.items.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(i) = self.state.selected()
+            && let Some((_, selected)) = self.items.get_mut(i)
+        {
+            *selected = !*selected;
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        for (_, selected) in &mut self.items {
+            *selected = true;
+        }
+    }
+
+    pub fn clear_all(&mut self) {
+        for (_, selected) in &mut self.items {
+            *selected = false;
+        }
+    }
+
+    pub fn selected(&self) -> impl Iterator<Item = &T> {
+        self.items
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(item, _)| item)
+    }
+
+    /// Navigation and selection keys shared by every list
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Up => self.previous(),
+            KeyCode::Down => self.next(),
+            KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+            KeyCode::Char('a') | KeyCode::Char('A') => self.select_all(),
+            KeyCode::Char('c') | KeyCode::Char('C') => self.clear_all(),
+            _ => {}
+        }
+    }
+
+    /// Drop the selected rows, e.g. once they have been burned or closed
+    pub fn remove_selected(&mut self) {
+        self.items.retain(|(_, selected)| !*selected);
+        if self.state.selected().is_some_and(|i| i >= self.items.len()) {
+            self.state.select(self.items.len().checked_sub(1));
+        }
+    }
+}
+
+impl<T: ListRow> SelectableList<T> {
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, title: &str) {
+        let columns = T::columns();
+        let header = Row::new(
+            std::iter::once("Selected")
+                .chain(columns.iter().map(|(title, _)| *title))
+                .map(Cell::from),
+        )
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+        let rows: Vec<Row> = self
+            .items
+            .iter()
+            .map(|(item, selected)| {
+                Row::new(
+                    std::iter::once(if *selected { "✓" } else { " " }.to_string())
+                        .chain(item.cells())
+                        .map(Cell::from),
+                )
+            })
+            .collect();
+        let widths: Vec<Constraint> = std::iter::once(Constraint::Length(8))
+            .chain(columns.iter().map(|(_, width)| *width))
+            .collect();
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("{} ({})", title, self.items.len()))
+                    .border_style(Style::default().fg(Color::Green)),
+            )
+            .row_highlight_style(Style::default().bg(Color::DarkGray));
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
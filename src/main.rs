@@ -1,48 +1,510 @@
-use clap::Parser;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::{Parser, Subcommand};
 use color_eyre::Result;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use futures::{FutureExt, StreamExt};
 use ratatui::{
     DefaultTerminal, Frame,
+    layout::Constraint,
     widgets::{Paragraph, TableState},
 };
+use solana_account_decoder::parse_token_extension::UiExtension;
 use solana_client::rpc_client::RpcClient;
+use solana_derivation_path::DerivationPath;
 use solana_sdk::{
+    hash::Hash,
     instruction::Instruction,
+    message::Message,
+    native_token::LAMPORTS_PER_SOL,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     transaction::Transaction,
 };
-use spl_token::instruction::close_account;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::instruction::close_account;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+use das::{CompressedAsset, DasClient};
+use keys::{KeySource, SeedPhraseOptions};
+use list::{ListRow, SelectableList};
+use multisig::MultisigAuthority;
+use offline::{DurableNonce, TransactionBundle, TxEncoding};
+use plan::{Fetched, Planner};
+use secret::{LockedKeypair, SecretString};
+
+mod batch;
+mod bubblegum;
+mod confidential;
+mod das;
+mod keys;
+mod keystore;
+mod list;
+mod metaplex;
+mod multisig;
+mod offline;
+mod plan;
+mod secret;
+
 #[derive(Parser)]
 #[command(name = "solana-burn-cli")]
 #[command(about = "A TUI tool for burning Solana tokens and closing ATA accounts")]
+#[command(subcommand_negates_reqs = true)]
 pub struct Args {
-    /// Private key in base58 format
-    #[arg(short, long)]
-    private_key: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    signer: SignerArgs,
+
+    /// Keypair file of a separate account paying transaction fees (defaults to the signer)
+    #[arg(long, value_name = "PATH", conflicts_with = "owner")]
+    fee_payer: Option<PathBuf>,
+
+    /// File receiving unsigned transactions in watch-only mode
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = "unsigned-transactions.json"
+    )]
+    export: PathBuf,
+
+    /// Encoding of exported transactions
+    #[arg(long, value_enum, default_value_t = TxEncoding::Base64)]
+    encoding: TxEncoding,
+
+    /// Account receiving the rent of closed accounts (defaults to the signer)
+    #[arg(long, value_name = "PUBKEY")]
+    rent_destination: Option<Pubkey>,
+
+    /// SPL Token multisig that owns, or is the close authority of, the accounts to process
+    #[arg(long, value_name = "PUBKEY")]
+    multisig: Option<Pubkey>,
+
+    /// Keypair file of a multisig member signing locally (repeatable)
+    #[arg(long = "cosigner", value_name = "PATH", requires = "multisig")]
+    cosigners: Vec<PathBuf>,
+
+    /// Multisig member signing later from the exported bundle (repeatable)
+    #[arg(long = "multisig-signer", value_name = "PUBKEY", requires = "multisig")]
+    multisig_signers: Vec<Pubkey>,
+
+    /// Recipient of balances from accounts whose action is "Transfer and close"
+    /// (set in the TUI with `S`)
+    #[arg(long, value_name = "PUBKEY")]
+    transfer_to: Option<Pubkey>,
+
+    /// Move balances of auxiliary token accounts into the owner's canonical ATA
+    /// instead of burning them (toggle in the TUI with `T`)
+    #[arg(long)]
+    consolidate: bool,
+
+    /// DAS (Digital Asset Standard) indexer URL used to list and burn compressed NFTs,
+    /// e.g. an RPC provider that supports `getAssetsByOwner`
+    #[arg(long, value_name = "URL")]
+    das_url: Option<String>,
+
+    /// Durable nonce accounts to use instead of a recent blockhash, one per transaction.
+    /// Transactions then stay valid until signed and broadcast.
+    #[arg(long = "nonce", value_name = "ACCOUNT")]
+    nonce_accounts: Vec<Pubkey>,
 
     /// Solana RPC endpoint URL
-    #[arg(short, long, default_value = "https://solana-rpc.publicnode.com")]
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value = "https://solana-rpc.publicnode.com"
+    )]
     rpc_url: String,
-    // Whether to burn tokens
-    // #[arg(long, default_value = "true")]
-    // burn_token: bool,
+    /// Whether to burn tokens (false for close-only mode)
+    #[arg(long, default_value = "true", action = clap::ArgAction::Set)]
+    burn_token: bool,
+
+    /// Whether to close token accounts (false for burn-only mode)
+    #[arg(long, default_value = "true", action = clap::ArgAction::Set)]
+    close_ata: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage password-encrypted signers stored in the config directory
+    #[command(subcommand)]
+    Keystore(KeystoreCommand),
+    /// Sign an exported transaction bundle offline with one signer
+    Sign {
+        /// Bundle written by watch-only mode or a previous `sign`
+        bundle: PathBuf,
+        /// Where to write the signed bundle (defaults to updating BUNDLE in place)
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        signer: SignerArgs,
+    },
+    /// Send a fully signed transaction bundle
+    Broadcast {
+        /// Bundle whose transactions carry every required signature
+        bundle: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeystoreCommand {
+    /// Encrypt a signer with a password and store it under NAME
+    Import {
+        name: String,
+        #[command(flatten)]
+        signer: SignerArgs,
+    },
+    /// List stored signers
+    List,
+    /// Delete a stored signer
+    Remove { name: String },
+}
+
+/// Where the signing key comes from
+#[derive(clap::Args)]
+#[group(skip)]
+#[command(group(
+    clap::ArgGroup::new("signer")
+        .required(true)
+        .args(["keypair", "private_key", "private_key_env", "private_key_prompt", "seed_phrase", "keystore", "owner"])
+))]
+struct SignerArgs {
+    /// Path to a Solana CLI keypair file (JSON array of 64 bytes)
+    #[arg(short, long)]
+    keypair: Option<PathBuf>,
+
+    /// Private key as base58, JSON byte array or hex (visible in shell history, prefer --keypair)
+    #[arg(short, long)]
+    private_key: Option<String>,
+
+    /// Name of an environment variable holding the private key
+    #[arg(long, value_name = "VAR")]
+    private_key_env: Option<String>,
+
+    /// Prompt for the private key without echo, or read it from stdin when piped
+    #[arg(long)]
+    private_key_prompt: bool,
+
+    /// Prompt for a BIP39 seed phrase, or read it from stdin when piped
+    #[arg(long)]
+    seed_phrase: bool,
+
+    /// Also prompt for the seed phrase's BIP39 passphrase
+    #[arg(long, requires = "seed_phrase")]
+    passphrase: bool,
+
+    /// Derivation path for the seed phrase
+    #[arg(long, default_value = "m/44'/501'/0'/0'", value_parser = keys::parse_derivation_path)]
+    derivation_path: DerivationPath,
+
+    /// Scan the first N `m/44'/501'/N'/0'` accounts and pick one by its token accounts
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "seed_phrase",
+        conflicts_with = "derivation_path"
+    )]
+    scan_accounts: Option<u32>,
+
+    /// Unlock the named entry of the encrypted keystore (see `keystore import`)
+    #[arg(long, value_name = "NAME")]
+    keystore: Option<String>,
+
+    /// Watch-only: list and plan for this owner without any secret key, exporting
+    /// unsigned transactions instead of sending them
+    #[arg(long, value_name = "PUBKEY")]
+    owner: Option<Pubkey>,
+}
+
+impl SignerArgs {
+    /// The signer source selected on the command line. An inline private key is moved
+    /// out of the arguments so no copy of it outlives the returned source.
+    fn take_key_source(&mut self) -> Option<KeySource> {
+        if let Some(path) = &self.keypair {
+            Some(KeySource::File(path.clone()))
+        } else if let Some(private_key) = self.private_key.take() {
+            Some(KeySource::Inline(SecretString::new(private_key)))
+        } else if let Some(name) = &self.private_key_env {
+            Some(KeySource::Env(name.clone()))
+        } else if self.private_key_prompt {
+            Some(KeySource::Prompt)
+        } else if self.seed_phrase {
+            Some(KeySource::SeedPhrase(SeedPhraseOptions {
+                derivation_path: self.derivation_path.clone(),
+                passphrase: self.passphrase,
+                scan: self.scan_accounts.unwrap_or(0),
+            }))
+        } else {
+            self.keystore.clone().map(KeySource::Keystore)
+        }
+    }
+}
+
+/// Operation awaiting confirmation in the dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfirmAction {
+    /// Burn and close the selected accounts
+    Process,
+    /// Revoke the delegates of the selected accounts, keeping the accounts open
+    RevokeDelegates,
+    /// Burn the selected compressed NFTs through Bubblegum
+    BurnCompressed,
+    /// Close the selected empty mints
+    CloseMints,
+    /// Close the selected upgradeable loader buffers
+    CloseBuffers,
+}
+
+/// View shown in the main area, switched with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tab {
+    Tokens,
+    Compressed,
+    Mints,
+    Buffers,
+}
+
+impl Tab {
+    const ALL: [Tab; 4] = [Tab::Tokens, Tab::Compressed, Tab::Mints, Tab::Buffers];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Tokens => "Token Accounts",
+            Tab::Compressed => "Compressed NFTs",
+            Tab::Mints => "Mints",
+            Tab::Buffers => "Buffers",
+        }
+    }
+
+    fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|tab| *tab == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// What processing does with one account, cycled per row with `X`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum AccountAction {
+    /// Follow the global mode (`M`)
+    #[default]
+    Default,
+    BurnAndClose,
+    /// Close an empty account without burning anything
+    CloseOnly,
+    /// Transfer the balance to the transfer destination, then close
+    TransferAndClose,
+    /// Revoke the delegate, keeping the account and its balance
+    RevokeOnly,
+}
+
+impl AccountAction {
+    fn next(self) -> Self {
+        match self {
+            Self::Default => Self::BurnAndClose,
+            Self::BurnAndClose => Self::CloseOnly,
+            Self::CloseOnly => Self::TransferAndClose,
+            Self::TransferAndClose => Self::RevokeOnly,
+            Self::RevokeOnly => Self::Default,
+        }
+    }
+}
 
-    // Whether to close ATA accounts
-    // #[arg(long, default_value = "true")]
-    // close_ata: bool,
+/// Address being typed in the destination prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DestinationField {
+    Rent,
+    Transfer,
 }
 
 #[derive(Debug, Clone)]
 struct TokenAccountInfo {
     address: Pubkey,
     mint: Pubkey,
+    /// Token program owning the account, SPL Token or Token-2022
+    program_id: Pubkey,
+    owner: Pubkey,
+    close_authority: Option<Pubkey>,
+    /// Delegate approved to spend `delegated_amount` from the account
+    delegate: Option<Pubkey>,
+    delegated_amount: u64,
+    ui_delegated_amount: String,
+    /// Frozen by the mint's freeze authority: can be neither burned nor closed
+    frozen: bool,
     balance: u64,
+    decimals: u8,
+    /// Whether this is the owner's associated token account for the mint, rather than
+    /// an auxiliary account created at another address
+    canonical: bool,
+    /// Token-2022 transfer hook on the mint; transfers need extra accounts
+    transfer_hook: bool,
+    /// Token-2022 transfer fees withheld in the account, which block closing it
+    withheld_amount: u64,
+    /// Token-2022 confidential balances that must be emptied before closing
+    needs_emptying: bool,
+    /// Token-2022 MemoTransfer: incoming transfers must be preceded by a memo
+    memo_required: bool,
+    /// Token-2022 CpiGuard: burning and closing through another program is blocked
+    cpi_guard: bool,
+    /// Extensions whose requirements for closing the tool cannot meet, by name
+    unsupported: Vec<String>,
     ui_balance: String,
+    /// Lamports held by the account: its rent, plus the wrapped SOL of WSOL accounts
+    lamports: u64,
+    /// Metaplex metadata of the mint, when the account holds an NFT
+    nft: Option<metaplex::Nft>,
+    /// Action chosen for this row
+    action: AccountAction,
+}
+
+impl TokenAccountInfo {
+    /// Wrapped SOL: the balance is lamports that closing returns, so it is never burned
+    fn is_native(&self) -> bool {
+        self.mint == spl_token::native_mint::id() || self.mint == spl_token_2022::native_mint::id()
+    }
+
+    /// Programmable NFT, kept frozen by Token Metadata rather than by the mint
+    fn is_programmable(&self) -> bool {
+        self.nft.as_ref().is_some_and(|nft| nft.is_programmable())
+    }
+
+    /// Why the account cannot be processed, if it cannot. The close authority and the
+    /// extensions only matter when `closing`; `signers` are the keys that can sign here or
+    /// through the export file.
+    fn blocked_reason(&self, closing: bool, signers: &[Pubkey]) -> Option<String> {
+        if self.frozen && !self.is_programmable() {
+            Some("it is frozen by the mint's freeze authority".to_string())
+        } else if !closing {
+            None
+        } else if let Some(close_authority) = self.close_authority
+            && !signers.contains(&close_authority)
+        {
+            Some(format!(
+                "its close authority {} cannot sign here",
+                close_authority
+            ))
+        } else if !self.unsupported.is_empty() {
+            Some(format!(
+                "of its extension(s): {}",
+                self.unsupported.join(", ")
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl ListRow for CompressedAsset {
+    fn columns() -> Vec<(&'static str, Constraint)> {
+        vec![
+            ("Name", Constraint::Percentage(30)),
+            ("Asset", Constraint::Percentage(20)),
+            ("Tree", Constraint::Percentage(20)),
+            ("Leaf", Constraint::Length(10)),
+            ("Delegate", Constraint::Percentage(20)),
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            format_address(&self.id),
+            format_address(&self.tree),
+            self.leaf_id.to_string(),
+            if self.delegate == self.owner {
+                String::new()
+            } else {
+                format_address(&self.delegate)
+            },
+        ]
+    }
+}
+
+/// Token-2022 mint with zero supply whose MintCloseAuthority we hold
+#[derive(Debug, Clone)]
+struct MintInfo {
+    address: Pubkey,
+    decimals: u8,
+    mint_authority: Option<Pubkey>,
+    /// Rent recovered by closing the mint
+    lamports: u64,
+}
+
+impl ListRow for MintInfo {
+    fn columns() -> Vec<(&'static str, Constraint)> {
+        vec![
+            ("Mint", Constraint::Percentage(40)),
+            ("Decimals", Constraint::Length(10)),
+            ("Mint Authority", Constraint::Percentage(30)),
+            ("Rent", Constraint::Percentage(20)),
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.address.to_string(),
+            self.decimals.to_string(),
+            self.mint_authority
+                .map_or("none".to_string(), |pubkey| format_address(&pubkey)),
+            format_sol(self.lamports),
+        ]
+    }
+}
+
+/// Upgradeable loader buffer left by a failed or abandoned program deploy
+#[derive(Debug, Clone)]
+struct BufferInfo {
+    address: Pubkey,
+    /// Account size in bytes, including the 37-byte buffer header
+    size: usize,
+    lamports: u64,
+}
+
+impl ListRow for BufferInfo {
+    fn columns() -> Vec<(&'static str, Constraint)> {
+        vec![
+            ("Buffer", Constraint::Percentage(50)),
+            ("Size", Constraint::Percentage(20)),
+            ("Balance", Constraint::Percentage(30)),
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.address.to_string(),
+            format!("{} bytes", self.size),
+            format_sol(self.lamports),
+        ]
+    }
+}
+
+/// Token programs whose accounts are listed
+const TOKEN_PROGRAMS: [Pubkey; 2] = [spl_token::ID, spl_token_2022::ID];
+
+/// Whether a base64 ElGamal ciphertext from a parsed account is the all-zero (empty) one
+fn is_zero_ciphertext(ciphertext: &str) -> bool {
+    BASE64
+        .decode(ciphertext)
+        .is_ok_and(|bytes| bytes.iter().all(|byte| *byte == 0))
+}
+
+/// Format address to show first 6 and last 4 characters with ellipsis
+fn format_address(pubkey: &Pubkey) -> String {
+    let addr_str = pubkey.to_string();
+    if addr_str.len() > 10 {
+        format!("{}...{}", &addr_str[..6], &addr_str[addr_str.len() - 4..])
+    } else {
+        addr_str
+    }
+}
+
+/// Format lamports as SOL with four decimals
+fn format_sol(lamports: u64) -> String {
+    format!("{:.4} SOL", lamports as f64 / LAMPORTS_PER_SOL as f64)
 }
 
 #[tokio::main]
@@ -50,24 +512,111 @@ async fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
     let args = Args::parse();
 
+    match args.command {
+        Some(Command::Keystore(command)) => return run_keystore_command(command),
+        Some(Command::Sign {
+            bundle,
+            output,
+            mut signer,
+        }) => {
+            if signer.owner.is_some() {
+                return Err(color_eyre::eyre::eyre!(
+                    "--owner is watch-only and cannot sign, give a signer source"
+                ));
+            }
+            if signer.scan_accounts.is_some() {
+                return Err(color_eyre::eyre::eyre!(
+                    "--scan-accounts is not supported when signing, use --derivation-path"
+                ));
+            }
+            let keypair = signer
+                .take_key_source()
+                .ok_or_else(|| color_eyre::eyre::eyre!("No signer source given"))?
+                .load()?;
+            let keypair = LockedKeypair::new(keypair);
+            let output = output.unwrap_or_else(|| bundle.clone());
+            return offline::sign_bundle(&bundle, &output, &keypair);
+        }
+        Some(Command::Broadcast { bundle }) => {
+            return offline::broadcast_bundle(&bundle, &RpcClient::new(args.rpc_url));
+        }
+        None => {}
+    }
+
+    // Build the app before taking over the terminal so key prompts and errors stay readable
+    let app = App::new(args).await?;
     let terminal = ratatui::init();
-    let result = App::new(args).await?.run(terminal).await;
+    let result = app.run(terminal).await;
     ratatui::restore();
     result
 }
 
+fn run_keystore_command(command: KeystoreCommand) -> Result<()> {
+    match command {
+        KeystoreCommand::Import { name, mut signer } => {
+            if signer.scan_accounts.is_some() {
+                return Err(color_eyre::eyre::eyre!(
+                    "--scan-accounts is not supported when importing, use --derivation-path"
+                ));
+            }
+            let keypair = signer
+                .take_key_source()
+                .ok_or_else(|| color_eyre::eyre::eyre!("No signer source given"))?
+                .load()?;
+            let path = keystore::import(&name, &keypair)?;
+            println!(
+                "Stored {} as {:?} in {}",
+                keypair.pubkey(),
+                name,
+                path.display()
+            );
+        }
+        KeystoreCommand::List => {
+            for (name, pubkey) in keystore::list()? {
+                println!("{:<20} {}", name, pubkey);
+            }
+        }
+        KeystoreCommand::Remove { name } => {
+            keystore::remove(&name)?;
+            println!("Removed keystore entry {:?}", name);
+        }
+    }
+    Ok(())
+}
+
 pub struct App {
     /// Is the application running?
     running: bool,
     /// Event stream
     event_stream: EventStream,
-    /// Keypair from private key
-    keypair: Keypair,
+    /// Owner of the token accounts
+    owner: Pubkey,
+    /// Keypair of the owner, locked in memory and wiped on drop. `None` in watch-only mode.
+    keypair: Option<LockedKeypair>,
+    /// Multisig owning or closing the listed accounts, and its members signing locally
+    multisig: Option<MultisigAuthority>,
+    cosigners: Vec<LockedKeypair>,
+    /// Durable nonce accounts, one per transaction, replacing the recent blockhash
+    nonce_accounts: Vec<Pubkey>,
+    /// Where watch-only mode writes unsigned transactions, and their encoding
+    export_path: PathBuf,
+    export_encoding: TxEncoding,
+    /// Separate fee payer, `None` when the signer pays its own fees
+    fee_payer: Option<LockedKeypair>,
+    /// SOL balances in lamports of the signer and the fee payer
+    owner_lamports: u64,
+    fee_payer_lamports: u64,
+    /// Account receiving the rent of closed accounts
+    rent_destination: Pubkey,
+    /// Recipient of balances from accounts set to "Transfer and close"
+    transfer_destination: Option<Pubkey>,
     /// RPC client
     rpc_client: RpcClient,
     /// Configuration
     burn_token: bool,
     close_ata: bool,
+    /// Transfer balances of auxiliary accounts to the canonical ATA instead of burning
+    consolidate: bool,
     /// All token accounts with selection status
     token_accounts: Vec<(TokenAccountInfo, bool)>, // (account, is_selected)
     /// Filtered token accounts based on search
@@ -78,71 +627,524 @@ pub struct App {
     search_mode: bool,
     /// Search input string
     search_input: String,
+    /// Destination editing state, and which destination is being edited
+    destination_mode: bool,
+    destination_field: DestinationField,
+    /// Destination input string
+    destination_input: String,
     /// Confirmation dialog state
     show_confirmation: bool,
     /// Number of selected accounts for confirmation
     selected_count_for_confirmation: usize,
+    /// What the open confirmation dialog is for
+    confirm_action: ConfirmAction,
+    /// Second confirmation step when rent goes to an account other than the signer
+    confirm_external_destination: bool,
+    /// Result or error of the last operation, shown in the footer
+    status_message: Option<String>,
+    /// View shown in the main area
+    tab: Tab,
+    /// Indexer listing compressed NFTs, if configured
+    das: Option<DasClient>,
+    compressed_assets: SelectableList<CompressedAsset>,
+    /// Empty mints that can be closed, scanned for when the Mints tab is first opened
+    mints: SelectableList<MintInfo>,
+    mints_loaded: bool,
+    /// Program buffers whose authority is the signer, scanned for when the Buffers tab is
+    /// first opened
+    buffers: SelectableList<BufferInfo>,
+    buffers_loaded: bool,
 }
 
 impl App {
     /// Construct a new instance of [`App`].
-    pub async fn new(args: Args) -> Result<Self> {
-        // Decode private key
-        let private_key_bytes = bs58::decode(&args.private_key)
-            .into_vec()
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to decode private key: {}", e))?;
+    pub async fn new(mut args: Args) -> Result<Self> {
+        // Create RPC client
+        let rpc_client = RpcClient::new(args.rpc_url.clone());
+
+        let keypair = match args.signer.take_key_source() {
+            Some(source) => {
+                let mut candidates = source.load_candidates()?;
+                let keypair = if candidates.len() == 1 {
+                    candidates.remove(0).1
+                } else {
+                    Self::pick_keypair(&rpc_client, candidates)?
+                };
+                let keypair = LockedKeypair::new(keypair);
+                if !keypair.is_locked() {
+                    eprintln!(
+                        "Warning: could not lock the signing key in memory, it may be swapped to disk"
+                    );
+                }
+                Some(keypair)
+            }
+            None => None,
+        };
+        let owner = match (&keypair, args.signer.owner) {
+            (Some(keypair), _) => keypair.pubkey(),
+            (None, Some(owner)) => owner,
+            (None, None) => return Err(color_eyre::eyre::eyre!("No signer source given")),
+        };
 
-        let keypair = Keypair::try_from(&private_key_bytes[..])
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to create keypair: {}", e))?;
+        let fee_payer = args
+            .fee_payer
+            .as_deref()
+            .map(keys::read_keypair)
+            .transpose()?
+            .filter(|fee_payer| fee_payer.pubkey() != owner)
+            .map(LockedKeypair::new);
 
-        // Create RPC client
-        let rpc_client = RpcClient::new(args.rpc_url);
+        let cosigners = args
+            .cosigners
+            .iter()
+            .map(|path| keys::read_keypair(path).map(LockedKeypair::new))
+            .collect::<Result<Vec<_>>>()?;
+        let multisig = match args.multisig {
+            Some(account) => {
+                let local = keypair
+                    .iter()
+                    .chain(&cosigners)
+                    .map(|keypair| keypair.pubkey())
+                    .collect::<Vec<_>>();
+                Some(MultisigAuthority::load(
+                    &rpc_client,
+                    &account,
+                    &local,
+                    &args.multisig_signers,
+                )?)
+            }
+            None => None,
+        };
+
+        if !args.burn_token && !args.close_ata {
+            return Err(color_eyre::eyre::eyre!(
+                "--burn-token false and --close-ata false together leave nothing to do"
+            ));
+        }
+
+        let rent_destination = args.rent_destination.unwrap_or(owner);
 
         let mut app = Self {
             running: false,
             event_stream: EventStream::new(),
+            owner,
             keypair,
+            multisig,
+            cosigners,
+            nonce_accounts: args.nonce_accounts,
+            export_path: args.export,
+            export_encoding: args.encoding,
+            rent_destination,
+            transfer_destination: args.transfer_to,
+            fee_payer,
+            owner_lamports: 0,
+            fee_payer_lamports: 0,
             rpc_client,
-            burn_token: true,
-            close_ata: true,
+            burn_token: args.burn_token,
+            close_ata: args.close_ata,
+            consolidate: args.consolidate,
             token_accounts: Vec::new(),
             filtered_accounts: Vec::new(),
             table_state: TableState::default(),
             search_mode: false,
             search_input: String::new(),
+            destination_mode: false,
+            destination_field: DestinationField::Rent,
+            destination_input: String::new(),
             show_confirmation: false,
             selected_count_for_confirmation: 0,
+            confirm_action: ConfirmAction::Process,
+            confirm_external_destination: false,
+            status_message: None,
+            tab: Tab::Tokens,
+            das: args.das_url.map(DasClient::new),
+            compressed_assets: SelectableList::default(),
+            mints: SelectableList::default(),
+            mints_loaded: false,
+            buffers: SelectableList::default(),
+            buffers_loaded: false,
         };
 
         // Load token accounts
-        app.load_token_accounts().await?;
+        app.load_token_accounts()?;
+        app.load_compressed_assets();
+        app.load_balances()?;
 
         Ok(app)
     }
 
+    /// Let the user choose one of several derived keypairs by its token account count
+    fn pick_keypair(rpc_client: &RpcClient, candidates: Vec<(String, Keypair)>) -> Result<Keypair> {
+        println!("Scanning {} derivation paths...", candidates.len());
+        let mut counts = Vec::with_capacity(candidates.len());
+        for (i, (path, keypair)) in candidates.iter().enumerate() {
+            let count = Self::fetch_token_accounts(rpc_client, &keypair.pubkey())?.len();
+            println!(
+                "  [{}] {:<20} {} {} token account(s)",
+                i,
+                path,
+                keypair.pubkey(),
+                count
+            );
+            counts.push(count);
+        }
+
+        // Default to the first wallet holding the most token accounts
+        let default = counts
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, count)| (**count, std::cmp::Reverse(*i)))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        print!("Select wallet [{}]: ", default);
+        std::io::Write::flush(&mut std::io::stdout())?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let index = match input.trim() {
+            "" => default,
+            choice => choice
+                .parse::<usize>()
+                .ok()
+                .filter(|i| *i < candidates.len())
+                .ok_or_else(|| color_eyre::eyre::eyre!("Invalid wallet selection: {}", choice))?,
+        };
+
+        Ok(candidates
+            .into_iter()
+            .nth(index)
+            .map(|(_, keypair)| keypair)
+            .expect("index checked above"))
+    }
+
     /// Load token accounts from Solana RPC
-    async fn load_token_accounts(&mut self) -> Result<()> {
-        let owner_pubkey = self.keypair.pubkey();
+    fn load_token_accounts(&mut self) -> Result<()> {
+        let mut accounts = Self::fetch_token_accounts(&self.rpc_client, &self.authority())?;
+        if let Some(multisig) = &self.multisig {
+            for account in Self::fetch_closable_accounts(&self.rpc_client, &multisig.account)? {
+                if !accounts.iter().any(|a| a.address == account.address) {
+                    accounts.push(account);
+                }
+            }
+        }
+
+        // Single tokens may be NFTs whose metadata the Token Metadata burn also closes
+        let candidates: Vec<(Pubkey, Pubkey)> = accounts
+            .iter()
+            .filter(|account| account.decimals == 0 && account.balance == 1)
+            .map(|account| (account.mint, account.address))
+            .collect();
+        let mut nfts = metaplex::load(&self.rpc_client, &candidates)?;
+        for account in &mut accounts {
+            account.nft = nfts.remove(&account.address);
+        }
+
+        self.token_accounts = accounts
+            .into_iter()
+            .map(|account| (account, false)) // Initially not selected
+            .collect();
+
+        // Initialize filtered accounts with all accounts
+        self.filtered_accounts = self.token_accounts.clone();
+
+        // Select first item if available
+        if !self.filtered_accounts.is_empty() {
+            self.table_state.select(Some(0));
+        }
+
+        Ok(())
+    }
+
+    /// List the owner's compressed NFTs from the DAS indexer, if one is configured.
+    /// Failures are reported in the footer so token accounts can still be processed.
+    fn load_compressed_assets(&mut self) {
+        let Some(das) = &self.das else {
+            return;
+        };
+        match das.compressed_assets(&self.owner) {
+            Ok(assets) => self.compressed_assets.set_items(assets),
+            Err(e) => self.status_message = Some(e.to_string()),
+        }
+    }
+
+    /// List the empty mints we can close. Failures are reported in the footer and the
+    /// scan is retried the next time the tab is opened.
+    fn load_mints(&mut self) {
+        match Self::fetch_closable_mints(&self.rpc_client, &self.authority()) {
+            Ok(mints) => {
+                self.mints.set_items(mints);
+                self.mints_loaded = true;
+            }
+            Err(e) => self.status_message = Some(e.to_string()),
+        }
+    }
 
-        // Get token accounts by owner
-        let accounts = self
+    /// List the program buffers the signer can close. Failures are reported in the footer
+    /// and the scan is retried the next time the tab is opened.
+    fn load_buffers(&mut self) {
+        match Self::fetch_buffers(&self.rpc_client, &self.owner) {
+            Ok(buffers) => {
+                self.buffers.set_items(buffers);
+                self.buffers_loaded = true;
+            }
+            Err(e) => self.status_message = Some(e.to_string()),
+        }
+    }
+
+    /// Refresh the SOL balances of the signer and the fee payer
+    fn load_balances(&mut self) -> Result<()> {
+        self.owner_lamports = self
             .rpc_client
-            .get_token_accounts_by_owner(
-                &owner_pubkey,
-                solana_client::rpc_request::TokenAccountsFilter::ProgramId(spl_token::id()),
+            .get_balance(&self.owner)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to get balance: {}", e))?;
+        self.fee_payer_lamports = match &self.fee_payer {
+            Some(fee_payer) => self
+                .rpc_client
+                .get_balance(&fee_payer.pubkey())
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to get fee payer balance: {}", e))?,
+            None => self.owner_lamports,
+        };
+        Ok(())
+    }
+
+    /// The account paying transaction fees
+    fn fee_payer_pubkey(&self) -> Pubkey {
+        self.fee_payer
+            .as_ref()
+            .map(|fee_payer| fee_payer.pubkey())
+            .unwrap_or(self.owner)
+    }
+
+    /// The key that owns the listed accounts: the multisig if one is given, else the owner
+    fn authority(&self) -> Pubkey {
+        self.multisig
+            .as_ref()
+            .map(|multisig| multisig.account)
+            .unwrap_or(self.owner)
+    }
+
+    /// Keys that can authorize instructions: the authority, which signs through the
+    /// export file in watch-only and multisig mode, and every key held locally
+    fn available_signers(&self) -> Vec<Pubkey> {
+        std::iter::once(self.authority())
+            .chain(
+                self.keypair
+                    .iter()
+                    .chain(&self.fee_payer)
+                    .chain(&self.cosigners)
+                    .map(|keypair| keypair.pubkey()),
             )
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to get token accounts: {}", e))?;
-        println!("accounts: {}", accounts.len());
+            .collect()
+    }
+
+    /// What processing does with the listed accounts under the current modes
+    fn planner(&self) -> Planner<'_> {
+        Planner {
+            owner: self.owner,
+            authority: self.authority(),
+            multisig: self.multisig.as_ref(),
+            fee_payer: self.fee_payer_pubkey(),
+            rent_destination: self.rent_destination,
+            transfer_destination: self.transfer_destination,
+            burn_token: self.burn_token,
+            close_ata: self.close_ata,
+            consolidate: self.consolidate,
+            token_accounts: &self.token_accounts,
+        }
+    }
+
+    /// Fetch and parse all token accounts owned by `owner` under both token programs
+    fn fetch_token_accounts(
+        rpc_client: &RpcClient,
+        owner: &Pubkey,
+    ) -> Result<Vec<TokenAccountInfo>> {
+        let mut token_accounts = Vec::new();
+        for program_id in TOKEN_PROGRAMS {
+            // Get token accounts by owner
+            let accounts = rpc_client
+                .get_token_accounts_by_owner(
+                    owner,
+                    solana_client::rpc_request::TokenAccountsFilter::ProgramId(program_id),
+                )
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to get token accounts: {}", e))?;
+            token_accounts.extend(Self::parse_token_accounts(accounts)?);
+        }
+
+        Ok(token_accounts)
+    }
+
+    /// Fetch token accounts owned by someone else whose close authority is `authority`
+    fn fetch_closable_accounts(
+        rpc_client: &RpcClient,
+        authority: &Pubkey,
+    ) -> Result<Vec<TokenAccountInfo>> {
+        use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+        use solana_sdk::program_pack::Pack;
+
+        let mut token_accounts = Vec::new();
+        for program_id in TOKEN_PROGRAMS {
+            // Account layout: the close authority is a COption<Pubkey> at offset 129.
+            // Token-2022 accounts with extensions are longer than the base layout.
+            let mut filters = vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(129, &[1, 0, 0, 0])),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(133, authority.as_ref())),
+            ];
+            if program_id == spl_token::id() {
+                filters.push(RpcFilterType::DataSize(
+                    spl_token::state::Account::LEN as u64,
+                ));
+            }
+            let config = serde_json::json!({
+                "encoding": "jsonParsed",
+                "filters": filters,
+            });
+            let accounts = rpc_client
+                .send(
+                    solana_client::rpc_request::RpcRequest::GetProgramAccounts,
+                    serde_json::json!([program_id.to_string(), config]),
+                )
+                .map_err(|e| {
+                    color_eyre::eyre::eyre!(
+                        "Failed to get accounts closable by {}: {}",
+                        authority,
+                        e
+                    )
+                })?;
+            token_accounts.extend(Self::parse_token_accounts(accounts)?);
+        }
+
+        Ok(token_accounts)
+    }
+
+    /// Token-2022 mints with zero supply whose MintCloseAuthority is `authority`.
+    /// Extension offsets vary, so candidates are mints whose mint authority is
+    /// `authority` or whose first extension is its MintCloseAuthority.
+    fn fetch_closable_mints(rpc_client: &RpcClient, authority: &Pubkey) -> Result<Vec<MintInfo>> {
+        use solana_account_decoder::UiAccountEncoding;
+        use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+        use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+        use spl_token_2022::extension::{
+            BaseStateWithExtensions, StateWithExtensions, mint_close_authority::MintCloseAuthority,
+        };
+
+        // Mint layout: mint authority COption<Pubkey> at 0, supply at 36. Extensions
+        // follow the 165-byte base, an account type byte (1 for mints), then TLV entries.
+        let mut close_authority_entry = vec![3, 0, 32, 0];
+        close_authority_entry.extend_from_slice(authority.as_ref());
+        let candidates = [
+            vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[1, 0, 0, 0])),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(4, authority.as_ref())),
+            ],
+            vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                166,
+                &close_authority_entry,
+            ))],
+        ];
+
+        let mut mints: Vec<MintInfo> = Vec::new();
+        for mut filters in candidates {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                36, &[0; 8],
+            )));
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(165, &[1])));
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+            let accounts = rpc_client
+                .get_program_accounts_with_config(&spl_token_2022::id(), config)
+                .map_err(|e| {
+                    color_eyre::eyre::eyre!("Failed to get mints closable by {}: {}", authority, e)
+                })?;
+
+            for (address, account) in accounts {
+                if mints.iter().any(|mint| mint.address == address) {
+                    continue;
+                }
+                let Ok(mint) =
+                    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data)
+                else {
+                    continue;
+                };
+                let close_authority = mint
+                    .get_extension::<MintCloseAuthority>()
+                    .ok()
+                    .and_then(|extension| Option::<Pubkey>::from(extension.close_authority));
+                if close_authority != Some(*authority) || mint.base.supply != 0 {
+                    continue;
+                }
+                mints.push(MintInfo {
+                    address,
+                    decimals: mint.base.decimals,
+                    mint_authority: mint.base.mint_authority.into(),
+                    lamports: account.lamports,
+                });
+            }
+        }
+
+        Ok(mints)
+    }
+
+    /// Upgradeable loader buffers whose authority is `authority`
+    fn fetch_buffers(rpc_client: &RpcClient, authority: &Pubkey) -> Result<Vec<BufferInfo>> {
+        use solana_account_decoder::UiAccountEncoding;
+        use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+        use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+
+        // Buffer layout: UpgradeableLoaderState tag 1 (Buffer) as a u32, then the
+        // authority as an Option<Pubkey>
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[1, 0, 0, 0])),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(4, &[1])),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(5, authority.as_ref())),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = rpc_client
+            .get_program_accounts_with_config(&solana_sdk_ids::bpf_loader_upgradeable::id(), config)
+            .map_err(|e| {
+                color_eyre::eyre::eyre!("Failed to get buffers of {}: {}", authority, e)
+            })?;
+
+        Ok(accounts
+            .into_iter()
+            .map(|(address, account)| BufferInfo {
+                address,
+                size: account.data.len(),
+                lamports: account.lamports,
+            })
+            .collect())
+    }
 
-        self.token_accounts.clear();
+    fn parse_token_accounts(
+        accounts: Vec<solana_client::rpc_response::RpcKeyedAccount>,
+    ) -> Result<Vec<TokenAccountInfo>> {
+        let mut token_accounts = Vec::with_capacity(accounts.len());
 
         for account in accounts {
             // Parse token account data
 
+            let program_id = Pubkey::from_str(&account.account.owner)
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to parse program id: {}", e))?;
+            // The RPC falls back to binary encoding for data it cannot parse
             let solana_account_decoder::UiAccountData::Json(account_data) = account.account.data
             else {
-                panic!("Failed to parse account data");
+                continue;
             };
+            // Program account scans can also match mints and multisigs
+            if account_data.parsed.get("type").and_then(|t| t.as_str()) != Some("account") {
+                continue;
+            }
 
             let info = account_data
                 .parsed
@@ -156,6 +1158,12 @@ impl App {
                 .and_then(|amount| amount.as_str())
                 .and_then(|amount| amount.parse::<u64>().ok())
                 .ok_or_else(|| color_eyre::eyre::eyre!("Failed to parse token amount"))?;
+            let decimals = info
+                .get("tokenAmount")
+                .and_then(|ta| ta.get("decimals"))
+                .and_then(|decimals| decimals.as_u64())
+                .and_then(|decimals| u8::try_from(decimals).ok())
+                .ok_or_else(|| color_eyre::eyre::eyre!("Failed to parse decimals"))?;
             let ui_token_amount = info
                 .get("tokenAmount")
                 .and_then(|token_amount| token_amount.as_object())
@@ -163,33 +1171,119 @@ impl App {
                 .and_then(|uas| uas.as_str())
                 .ok_or_else(|| color_eyre::eyre::eyre!("Failed to parse ui token amount"))?
                 .to_string();
+            let delegate = info
+                .get("delegate")
+                .and_then(|delegate| delegate.as_str())
+                .and_then(|delegate| Pubkey::from_str(delegate).ok());
+            let delegated_amount = info
+                .get("delegatedAmount")
+                .and_then(|amount| amount.get("amount"))
+                .and_then(|amount| amount.as_str())
+                .and_then(|amount| amount.parse::<u64>().ok())
+                .unwrap_or(0);
+            let ui_delegated_amount = info
+                .get("delegatedAmount")
+                .and_then(|amount| amount.get("uiAmountString"))
+                .and_then(|amount| amount.as_str())
+                .unwrap_or("0")
+                .to_string();
+            let frozen = info.get("state").and_then(|state| state.as_str()) == Some("frozen");
             let mint = info
                 .get("mint")
                 .and_then(|mint| mint.as_str())
                 .ok_or_else(|| color_eyre::eyre::eyre!("Failed to parse mint"))?
                 .to_string();
+            let owner = info
+                .get("owner")
+                .and_then(|owner| owner.as_str())
+                .and_then(|owner| Pubkey::from_str(owner).ok())
+                .ok_or_else(|| color_eyre::eyre::eyre!("Failed to parse owner"))?;
+            let close_authority = info
+                .get("closeAuthority")
+                .and_then(|authority| authority.as_str())
+                .and_then(|authority| Pubkey::from_str(authority).ok());
+            let extensions = info
+                .get("extensions")
+                .and_then(|extensions| extensions.as_array())
+                .map(|extensions| extensions.as_slice())
+                .unwrap_or_default();
+            let mut withheld_amount = 0;
+            let mut needs_emptying = false;
+            let mut memo_required = false;
+            let mut cpi_guard = false;
+            let mut transfer_hook = false;
+            let mut unsupported = Vec::new();
+            for raw in extensions {
+                let name = raw
+                    .get("extension")
+                    .and_then(|name| name.as_str())
+                    .unwrap_or("unknown");
+                match serde_json::from_value(raw.clone()) {
+                    Ok(UiExtension::TransferFeeAmount(fees)) => {
+                        withheld_amount = fees.withheld_amount
+                    }
+                    Ok(UiExtension::ConfidentialTransferAccount(confidential)) => {
+                        needs_emptying = [
+                            &confidential.pending_balance_lo,
+                            &confidential.pending_balance_hi,
+                            &confidential.available_balance,
+                        ]
+                        .iter()
+                        .any(|ciphertext| !is_zero_ciphertext(ciphertext))
+                    }
+                    Ok(UiExtension::MemoTransfer(memo)) => {
+                        memo_required = memo.require_incoming_transfer_memos
+                    }
+                    Ok(UiExtension::CpiGuard(guard)) => cpi_guard = guard.lock_cpi,
+                    // Withheld confidential fees can only be withdrawn by the mint's authority
+                    Ok(UiExtension::ConfidentialTransferFeeAmount(fees)) => {
+                        if !is_zero_ciphertext(&fees.withheld_amount) {
+                            unsupported.push(name.to_string());
+                        }
+                    }
+                    Ok(UiExtension::TransferHookAccount(_)) => transfer_hook = true,
+                    // Neither burning nor closing is affected by these
+                    Ok(
+                        UiExtension::ImmutableOwner
+                        | UiExtension::NonTransferableAccount
+                        | UiExtension::PausableAccount,
+                    ) => {}
+                    _ => unsupported.push(name.to_string()),
+                }
+            }
+            let address = Pubkey::from_str(&account.pubkey)
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to parse pubkey: {}", e))?;
+            let mint = Pubkey::from_str_const(&mint);
+            let canonical =
+                address == get_associated_token_address_with_program_id(&owner, &mint, &program_id);
             // TokenAccount size
-            self.token_accounts.push((
-                TokenAccountInfo {
-                    address: Pubkey::from_str(&account.pubkey)
-                        .map_err(|e| color_eyre::eyre::eyre!("Failed to parse pubkey: {}", e))?,
-                    mint: Pubkey::from_str_const(&mint),
-                    balance: token_amount,
-                    ui_balance: ui_token_amount,
-                },
-                false,
-            )); // Initially not selected
-        }
-
-        // Initialize filtered accounts with all accounts
-        self.filtered_accounts = self.token_accounts.clone();
-
-        // Select first item if available
-        if !self.filtered_accounts.is_empty() {
-            self.table_state.select(Some(0));
+            token_accounts.push(TokenAccountInfo {
+                address,
+                mint,
+                program_id,
+                owner,
+                close_authority,
+                delegate,
+                delegated_amount,
+                ui_delegated_amount,
+                frozen,
+                balance: token_amount,
+                decimals,
+                canonical,
+                transfer_hook,
+                withheld_amount,
+                needs_emptying,
+                memo_required,
+                cpi_guard,
+                unsupported,
+                ui_balance: ui_token_amount,
+                lamports: account.account.lamports,
+                nft: None,
+                action: AccountAction::Default,
+            });
         }
 
-        Ok(())
+        Ok(token_accounts)
     }
 
     /// Run the application's main loop.
@@ -204,27 +1298,72 @@ impl App {
 
     /// Renders the user interface.
     fn draw(&mut self, frame: &mut Frame) {
-        use ratatui::layout::{Constraint, Direction, Layout};
-        use ratatui::style::{Color, Style};
-        use ratatui::widgets::{Block, Borders};
+        use ratatui::layout::{Direction, Layout};
+        use ratatui::style::{Color, Modifier, Style};
+        use ratatui::widgets::{Block, Borders, Tabs};
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3), // Header
+                Constraint::Length(1), // Tabs
                 Constraint::Min(0),    // Main table
                 Constraint::Length(3), // Footer with instructions
             ])
             .split(frame.area());
 
         // Header with pubkey and settings
-        let pubkey_str = self.keypair.pubkey().to_string();
-        let selected_count = self
-            .filtered_accounts
-            .iter()
-            .filter(|(_, selected)| *selected)
-            .count();
-        let header_text = if self.search_mode {
+        let mut pubkey_str = format!(
+            "{} ({}){}",
+            self.owner,
+            format_sol(self.owner_lamports),
+            if self.keypair.is_none() {
+                " WATCH-ONLY"
+            } else {
+                ""
+            }
+        );
+        if let Some(multisig) = &self.multisig {
+            pubkey_str.push_str(&format!(
+                " | Multisig: {} ({} of {})",
+                format_address(&multisig.account),
+                multisig.threshold,
+                multisig.members
+            ));
+        }
+        if let Some(fee_payer) = &self.fee_payer {
+            pubkey_str.push_str(&format!(
+                " | Fee payer: {} ({})",
+                format_address(&fee_payer.pubkey()),
+                format_sol(self.fee_payer_lamports)
+            ));
+        }
+        let (selected_count, shown_count) = match self.tab {
+            Tab::Tokens => (
+                self.filtered_accounts
+                    .iter()
+                    .filter(|(_, selected)| *selected)
+                    .count(),
+                self.filtered_accounts.len(),
+            ),
+            Tab::Compressed => (
+                self.compressed_assets.selected().count(),
+                self.compressed_assets.items.len(),
+            ),
+            Tab::Mints => (self.mints.selected().count(), self.mints.items.len()),
+            Tab::Buffers => (self.buffers.selected().count(), self.buffers.items.len()),
+        };
+        let header_text = if self.destination_mode {
+            format!(
+                "Pubkey: {} | {}: {}",
+                pubkey_str,
+                match self.destination_field {
+                    DestinationField::Rent => "RENT DESTINATION",
+                    DestinationField::Transfer => "TRANSFER DESTINATION",
+                },
+                self.destination_input
+            )
+        } else if self.search_mode {
             format!(
                 "Pubkey: {} | SEARCH MODE: {} | Selected: {}/{} (Total: {})",
                 pubkey_str,
@@ -235,12 +1374,15 @@ impl App {
             )
         } else {
             format!(
-                "Pubkey: {} | Burn Token: {} | Close ATA: {} | Selected: {}/{}",
+                "Pubkey: {} | Mode: {} | Consolidate: {} | Rent To: {} | Transfer To: {} | Selected: {}/{}",
                 pubkey_str,
-                self.burn_token,
-                self.close_ata,
+                self.mode_label(),
+                self.consolidate,
+                self.format_rent_destination(),
+                self.transfer_destination
+                    .map_or("unset".to_string(), |pubkey| format_address(&pubkey)),
                 selected_count,
-                self.filtered_accounts.len()
+                shown_count
             )
         };
         let header = Paragraph::new(header_text)
@@ -252,21 +1394,68 @@ impl App {
             .style(Style::default().fg(Color::White));
         frame.render_widget(header, chunks[0]);
 
+        let tabs = Tabs::new(Tab::ALL.iter().map(|tab| tab.title()))
+            .select(Tab::ALL.iter().position(|tab| *tab == self.tab))
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            );
+        frame.render_widget(tabs, chunks[1]);
+
         // Main table
-        self.draw_main_table(frame, chunks[1]);
+        match self.tab {
+            Tab::Tokens => self.draw_main_table(frame, chunks[2]),
+            Tab::Compressed => {
+                let title = if self.das.is_some() {
+                    "Compressed NFTs"
+                } else {
+                    "Compressed NFTs: pass --das-url to list them"
+                };
+                self.compressed_assets.draw(frame, chunks[2], title);
+            }
+            Tab::Mints => self
+                .mints
+                .draw(frame, chunks[2], "Empty Mints We Can Close"),
+            Tab::Buffers => self
+                .buffers
+                .draw(frame, chunks[2], "Program Buffers We Can Close"),
+        }
 
-        // Footer with instructions
+        // Footer with instructions, or the outcome of the last operation
         let footer_text = if self.show_confirmation {
             "Confirmation: Y/Enter Confirm | N/Esc Cancel"
+        } else if self.destination_mode {
+            match self.destination_field {
+                DestinationField::Rent => {
+                    "Rent Destination: Type a pubkey (empty for signer) | Enter Save | Esc Cancel"
+                }
+                DestinationField::Transfer => {
+                    "Transfer Destination: Type the recipient's pubkey (empty to unset) | Enter Save | Esc Cancel"
+                }
+            }
         } else if self.search_mode {
             "Search Mode: Type to filter by mint | Enter/Esc Exit Search | ↑/↓ Navigate | Space Toggle"
+        } else if let Some(status) = &self.status_message {
+            status
+        } else if self.tab != Tab::Tokens {
+            "Controls: Tab Switch View | ↑/↓ Navigate | Space/Enter Toggle | A Select All | C Clear All | Ctrl+P Process | Q Quit"
+        } else {
+            "Controls: Tab Switch View | ↑/↓ Navigate | Space/Enter Toggle | A Select All | C Clear All | F Search | X Action | D Rent Destination | S Transfer Destination | M Mode | T Consolidate | R Revoke Delegates | Ctrl+P Process | Q Quit"
+        };
+        let (footer_title, footer_color) = if self.status_message.is_some()
+            && !self.show_confirmation
+            && !self.search_mode
+            && !self.destination_mode
+        {
+            ("Status (any key to dismiss)", Color::Cyan)
         } else {
-            "Controls: ↑/↓ Navigate | Space/Enter Toggle | A Select All | C Clear All | F Search | Ctrl+P Process | Q Quit"
+            ("Controls", Color::Yellow)
         };
         let footer = Paragraph::new(footer_text)
-            .block(Block::default().borders(Borders::ALL).title("Controls"))
-            .style(Style::default().fg(Color::Yellow));
-        frame.render_widget(footer, chunks[2]);
+            .block(Block::default().borders(Borders::ALL).title(footer_title))
+            .style(Style::default().fg(footer_color));
+        frame.render_widget(footer, chunks[3]);
 
         // Draw confirmation dialog if needed
         if self.show_confirmation {
@@ -280,8 +1469,13 @@ impl App {
 
         let header = Row::new(vec![
             Cell::from("Selected"),
+            Cell::from("Action"),
+            Cell::from("Type"),
             Cell::from("Address"),
             Cell::from("Mint"),
+            Cell::from("Program"),
+            Cell::from("Delegate"),
+            Cell::from("Extensions"),
             Cell::from("Balance"),
         ])
         .style(
@@ -295,16 +1489,74 @@ impl App {
             .iter()
             .map(|(account, is_selected)| {
                 let selected_str = if *is_selected { "✓" } else { " " };
-                let address_str = self.format_address(&account.address);
-                let mint_str = self.format_address(&account.mint);
-                let balance_str = &account.ui_balance;
+                let address_str = format_address(&account.address);
+                let mint_str = if account.is_native() {
+                    format!("WSOL {}", format_address(&account.mint))
+                } else if account.is_programmable() {
+                    format!("pNFT {}", format_address(&account.mint))
+                } else if account.nft.is_some() {
+                    format!("NFT {}", format_address(&account.mint))
+                } else {
+                    format_address(&account.mint)
+                };
+                let balance_str = if account.needs_emptying {
+                    format!("{} (needs emptying)", account.ui_balance)
+                } else {
+                    account.ui_balance.clone()
+                };
+                let program_str = if account.program_id == spl_token_2022::id() {
+                    "Token-2022"
+                } else {
+                    "Token"
+                };
+
+                let delegate_str = match &account.delegate {
+                    Some(delegate) => format!(
+                        "{} ({})",
+                        format_address(delegate),
+                        account.ui_delegated_amount
+                    ),
+                    None => String::new(),
+                };
+                let mut extensions = Vec::new();
+                if account.frozen && !account.is_programmable() {
+                    extensions.push("frozen".to_string());
+                }
+                if account.memo_required {
+                    extensions.push("memo".to_string());
+                }
+                if account.cpi_guard {
+                    extensions.push("cpi-guard".to_string());
+                }
+                extensions.extend(account.unsupported.iter().map(|name| format!("!{}", name)));
+
+                // Actions chosen per row stand out from those following the mode
+                let action_cell = Cell::from(self.action_label(account));
+                let action_cell = if account.action == AccountAction::Default {
+                    action_cell
+                } else {
+                    action_cell.style(Style::default().fg(Color::Cyan))
+                };
 
-                Row::new(vec![
+                let row = Row::new(vec![
                     Cell::from(selected_str),
+                    action_cell,
+                    Cell::from(if account.canonical { "ATA" } else { "Aux" }),
                     Cell::from(address_str),
                     Cell::from(mint_str),
-                    Cell::from(balance_str.clone()),
-                ])
+                    Cell::from(program_str),
+                    Cell::from(delegate_str),
+                    Cell::from(extensions.join(", ")),
+                    Cell::from(balance_str),
+                ]);
+                // Frozen accounts are greyed out, other accounts the tool cannot close are red
+                if account.frozen && !account.is_programmable() {
+                    row.style(Style::default().add_modifier(Modifier::DIM))
+                } else if !account.unsupported.is_empty() {
+                    row.style(Style::default().fg(Color::Red))
+                } else {
+                    row
+                }
             })
             .collect();
 
@@ -312,9 +1564,14 @@ impl App {
             rows,
             [
                 ratatui::layout::Constraint::Length(8),      // Selected column
-                ratatui::layout::Constraint::Percentage(30), // Address
-                ratatui::layout::Constraint::Percentage(30), // Mint
-                ratatui::layout::Constraint::Percentage(40), // Balance
+                ratatui::layout::Constraint::Length(18),     // Action
+                ratatui::layout::Constraint::Length(4),      // Type
+                ratatui::layout::Constraint::Percentage(18), // Address
+                ratatui::layout::Constraint::Percentage(18), // Mint
+                ratatui::layout::Constraint::Length(10),     // Program
+                ratatui::layout::Constraint::Percentage(20), // Delegate
+                ratatui::layout::Constraint::Percentage(14), // Extensions
+                ratatui::layout::Constraint::Percentage(30), // Balance
             ],
         )
         .header(header)
@@ -329,16 +1586,6 @@ impl App {
         frame.render_stateful_widget(table, area, &mut self.table_state);
     }
 
-    /// Format address to show first 6 and last 4 characters with ellipsis
-    fn format_address(&self, pubkey: &Pubkey) -> String {
-        let addr_str = pubkey.to_string();
-        if addr_str.len() > 10 {
-            format!("{}...{}", &addr_str[..6], &addr_str[addr_str.len() - 4..])
-        } else {
-            addr_str
-        }
-    }
-
     /// Reads the crossterm events and updates the state of [`App`].
     async fn handle_crossterm_events(&mut self) -> Result<()> {
         tokio::select! {
@@ -363,8 +1610,15 @@ impl App {
 
     /// Handles the key events and updates the state of [`App`].
     fn on_key_event(&mut self, key: KeyEvent) {
+        // Any key dismisses the status message before acting normally
+        if !self.show_confirmation {
+            self.status_message = None;
+        }
+
         if self.show_confirmation {
             self.handle_confirmation_keys(key);
+        } else if self.destination_mode {
+            self.handle_destination_mode_keys(key);
         } else if self.search_mode {
             self.handle_search_mode_keys(key);
         } else {
@@ -402,10 +1656,68 @@ impl App {
         }
     }
 
-    fn handle_normal_mode_keys(&mut self, key: KeyEvent) {
-        match (key.modifiers, key.code) {
-            (_, KeyCode::Esc | KeyCode::Char('q'))
-            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+    fn handle_destination_mode_keys(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                self.destination_mode = false;
+                self.destination_input.clear();
+            }
+            KeyCode::Enter => {
+                self.save_destination();
+            }
+            KeyCode::Backspace => {
+                self.destination_input.pop();
+            }
+            KeyCode::Char(c) if !c.is_whitespace() => {
+                self.destination_input.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    /// Switch to the next view. Program account scans are slow and rejected by some
+    /// public RPCs, so they only run once their tab is opened.
+    fn next_tab(&mut self) {
+        self.tab = self.tab.next();
+        match self.tab {
+            Tab::Mints if !self.mints_loaded => self.load_mints(),
+            Tab::Buffers if !self.buffers_loaded => self.load_buffers(),
+            _ => {}
+        }
+    }
+
+    /// Keys of the tabs besides the token accounts, which only select and process rows
+    fn handle_list_keys(&mut self, key: KeyEvent) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc | KeyCode::Char('q'))
+            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+
+            (_, KeyCode::Tab) => self.next_tab(),
+
+            (KeyModifiers::CONTROL, KeyCode::Char('p') | KeyCode::Char('P')) => {
+                self.show_process_confirmation();
+            }
+
+            (_, code) => match self.tab {
+                Tab::Compressed => self.compressed_assets.handle_key(code),
+                Tab::Mints => self.mints.handle_key(code),
+                Tab::Buffers => self.buffers.handle_key(code),
+                Tab::Tokens => {}
+            },
+        }
+    }
+
+    fn handle_normal_mode_keys(&mut self, key: KeyEvent) {
+        if self.tab != Tab::Tokens {
+            self.handle_list_keys(key);
+            return;
+        }
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc | KeyCode::Char('q'))
+            | (KeyModifiers::CONTROL, KeyCode::Char('c') | KeyCode::Char('C')) => self.quit(),
+
+            // Tab to switch to the next view
+            (_, KeyCode::Tab) => self.next_tab(),
 
             // Arrow keys for navigation
             (_, KeyCode::Up) => {
@@ -435,6 +1747,44 @@ impl App {
                 self.enter_search_mode();
             }
 
+            // D to edit the rent destination
+            (_, KeyCode::Char('d') | KeyCode::Char('D')) => {
+                self.destination_mode = true;
+                self.destination_field = DestinationField::Rent;
+                self.destination_input.clear();
+            }
+
+            // S to edit the recipient of "Transfer and close" balances
+            (_, KeyCode::Char('s') | KeyCode::Char('S')) => {
+                self.destination_mode = true;
+                self.destination_field = DestinationField::Transfer;
+                self.destination_input.clear();
+            }
+
+            // X to cycle the action of the current row
+            (_, KeyCode::Char('x') | KeyCode::Char('X')) => {
+                self.cycle_action();
+            }
+
+            // M to cycle burn-and-close, close-only and burn-only
+            (_, KeyCode::Char('m') | KeyCode::Char('M')) => {
+                (self.burn_token, self.close_ata) = match (self.burn_token, self.close_ata) {
+                    (true, true) => (false, true),
+                    (false, true) => (true, false),
+                    _ => (true, true),
+                };
+            }
+
+            // T to toggle moving auxiliary balances into the canonical ATA
+            (_, KeyCode::Char('t') | KeyCode::Char('T')) => {
+                self.consolidate = !self.consolidate;
+            }
+
+            // R to revoke the delegates of the selected accounts, keeping the accounts
+            (_, KeyCode::Char('r') | KeyCode::Char('R')) => {
+                self.show_revoke_confirmation();
+            }
+
             // Ctrl+P to show confirmation for processing selected accounts
             (KeyModifiers::CONTROL, KeyCode::Char('p') | KeyCode::Char('P')) => {
                 self.show_process_confirmation();
@@ -478,26 +1828,54 @@ impl App {
     }
 
     fn toggle_selection(&mut self) {
-        if let Some(i) = self.table_state.selected() {
-            if i < self.filtered_accounts.len() {
-                let account_address = self.filtered_accounts[i].0.address;
-                // Find the account in the original list and toggle it
-                if let Some(pos) = self
-                    .token_accounts
-                    .iter()
-                    .position(|(acc, _)| acc.address == account_address)
-                {
-                    self.token_accounts[pos].1 = !self.token_accounts[pos].1;
-                    // Update the filtered list to reflect the change
-                    self.filtered_accounts[i].1 = self.token_accounts[pos].1;
-                }
+        if let Some(i) = self.table_state.selected()
+            && i < self.filtered_accounts.len()
+        {
+            let account_address = self.filtered_accounts[i].0.address;
+            // Find the account in the original list and toggle it
+            if let Some(pos) = self
+                .token_accounts
+                .iter()
+                .position(|(acc, _)| acc.address == account_address)
+            {
+                self.token_accounts[pos].1 = !self.token_accounts[pos].1;
+                // Update the filtered list to reflect the change
+                self.filtered_accounts[i].1 = self.token_accounts[pos].1;
+            }
+        }
+    }
+
+    fn cycle_action(&mut self) {
+        if let Some(i) = self.table_state.selected()
+            && i < self.filtered_accounts.len()
+        {
+            let account_address = self.filtered_accounts[i].0.address;
+            if let Some((account, _)) = self
+                .token_accounts
+                .iter_mut()
+                .find(|(acc, _)| acc.address == account_address)
+            {
+                account.action = account.action.next();
+                self.filtered_accounts[i].0.action = account.action;
             }
         }
     }
 
     fn select_all(&mut self) {
-        for (_, selected) in &mut self.token_accounts {
-            *selected = true;
+        // Accounts the planner would refuse are left out
+        let signers = self.available_signers();
+        let planner = self.planner();
+        let selectable: Vec<bool> = self
+            .token_accounts
+            .iter()
+            .map(|(account, _)| {
+                account
+                    .blocked_reason(planner.closes(account), &signers)
+                    .is_none()
+            })
+            .collect();
+        for ((_, selected), selectable) in self.token_accounts.iter_mut().zip(selectable) {
+            *selected = selectable;
         }
         // Update filtered accounts to reflect changes
         self.sync_filtered_accounts();
@@ -511,6 +1889,57 @@ impl App {
         self.sync_filtered_accounts();
     }
 
+    fn save_destination(&mut self) {
+        let input = self.destination_input.trim();
+        let pubkey = if input.is_empty() {
+            None
+        } else {
+            match Pubkey::from_str(input) {
+                Ok(pubkey) => Some(pubkey),
+                Err(e) => {
+                    self.status_message = Some(format!("Invalid destination {input}: {e}"));
+                    return;
+                }
+            }
+        };
+        match self.destination_field {
+            DestinationField::Rent => self.rent_destination = pubkey.unwrap_or(self.owner),
+            DestinationField::Transfer => self.transfer_destination = pubkey,
+        }
+        self.destination_mode = false;
+        self.destination_input.clear();
+    }
+
+    /// Name of the current combination of `burn_token` and `close_ata`
+    fn mode_label(&self) -> &'static str {
+        match (self.burn_token, self.close_ata) {
+            (true, true) => "Burn and close",
+            (false, true) => "Close only",
+            (true, false) => "Burn only",
+            (false, false) => "Nothing",
+        }
+    }
+
+    /// Label of the action `account` is processed with, resolving the default to the mode
+    fn action_label(&self, account: &TokenAccountInfo) -> &'static str {
+        match account.action {
+            AccountAction::Default => self.mode_label(),
+            AccountAction::BurnAndClose => "Burn and close",
+            AccountAction::CloseOnly => "Close only",
+            AccountAction::TransferAndClose => "Transfer and close",
+            AccountAction::RevokeOnly => "Revoke only",
+        }
+    }
+
+    /// Short rent destination label, marking whether it is the signer itself
+    fn format_rent_destination(&self) -> String {
+        if self.rent_destination == self.owner {
+            "signer".to_string()
+        } else {
+            format_address(&self.rent_destination)
+        }
+    }
+
     fn enter_search_mode(&mut self) {
         self.search_mode = true;
         self.search_input.clear();
@@ -553,39 +1982,100 @@ impl App {
     }
 
     fn sync_filtered_accounts(&mut self) {
-        // Update filtered accounts to reflect selection changes in token_accounts
+        // Update filtered accounts to reflect changes in token_accounts
         for (filtered_account, filtered_selected) in &mut self.filtered_accounts {
-            if let Some((_, selected)) = self
+            if let Some((account, selected)) = self
                 .token_accounts
                 .iter()
                 .find(|(acc, _)| acc.address == filtered_account.address)
             {
                 *filtered_selected = *selected;
+                filtered_account.clone_from(account);
             }
         }
     }
 
     fn show_process_confirmation(&mut self) {
-        let selected_count = self
+        let (selected_count, action) = match self.tab {
+            Tab::Tokens => (
+                self.token_accounts
+                    .iter()
+                    .filter(|(_, selected)| *selected)
+                    .count(),
+                ConfirmAction::Process,
+            ),
+            Tab::Compressed => (
+                self.compressed_assets.selected().count(),
+                ConfirmAction::BurnCompressed,
+            ),
+            Tab::Mints => (self.mints.selected().count(), ConfirmAction::CloseMints),
+            Tab::Buffers => (self.buffers.selected().count(), ConfirmAction::CloseBuffers),
+        };
+
+        if selected_count > 0 {
+            self.selected_count_for_confirmation = selected_count;
+            self.confirm_action = action;
+            self.confirm_external_destination = false;
+            self.show_confirmation = true;
+        }
+    }
+
+    fn show_revoke_confirmation(&mut self) {
+        let delegated_count = self
             .token_accounts
             .iter()
-            .filter(|(_, selected)| *selected)
+            .filter(|(account, selected)| *selected && account.delegate.is_some())
             .count();
 
-        if selected_count > 0 {
-            self.selected_count_for_confirmation = selected_count;
+        if delegated_count > 0 {
+            self.selected_count_for_confirmation = delegated_count;
+            self.confirm_action = ConfirmAction::RevokeDelegates;
+            self.confirm_external_destination = false;
             self.show_confirmation = true;
+        } else {
+            self.status_message = Some("No selected account has a delegate".to_string());
         }
     }
 
     fn handle_confirmation_keys(&mut self, key: KeyEvent) {
         match key.code {
             KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if self.confirm_action == ConfirmAction::RevokeDelegates {
+                    self.show_confirmation = false;
+                    self.revoke_selected();
+                    return;
+                }
+                if self.confirm_action == ConfirmAction::BurnCompressed {
+                    self.show_confirmation = false;
+                    self.burn_compressed_selected();
+                    return;
+                }
+                // Rent leaving the signer needs a second, explicit confirmation
+                let closes_any = matches!(
+                    self.confirm_action,
+                    ConfirmAction::CloseMints | ConfirmAction::CloseBuffers
+                ) || self
+                    .token_accounts
+                    .iter()
+                    .any(|(account, selected)| *selected && self.planner().closes(account));
+                if closes_any
+                    && self.rent_destination != self.owner
+                    && !self.confirm_external_destination
+                {
+                    self.confirm_external_destination = true;
+                    return;
+                }
                 self.show_confirmation = false;
-                self.process_selected();
+                self.confirm_external_destination = false;
+                match self.confirm_action {
+                    ConfirmAction::CloseMints => self.close_selected_mints(),
+                    ConfirmAction::CloseBuffers => self.close_selected_buffers(),
+                    _ => self.process_selected(),
+                }
             }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                 self.show_confirmation = false;
+                self.confirm_external_destination = false;
             }
             _ => {}
         }
@@ -596,13 +2086,201 @@ impl App {
         use ratatui::style::{Color, Style};
         use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
+        // Create the confirmation message
+        let destination_note = if self.rent_destination == self.owner {
+            "(the signer)"
+        } else {
+            "(NOT the signer)"
+        };
+        let outcome = match &self.keypair {
+            Some(_) => "This will".to_string(),
+            None => format!(
+                "Watch-only: unsigned transactions will be written to {} that",
+                self.export_path.display()
+            ),
+        };
+        let message = if self.confirm_action == ConfirmAction::RevokeDelegates {
+            format!(
+                "Revoke the delegates of {} selected account(s)?\n\nTokens are not burned and the accounts stay open.\nDelegates of frozen accounts cannot be revoked and are skipped.\n\nPress Y to confirm, N to cancel",
+                self.selected_count_for_confirmation
+            )
+        } else if self.confirm_action == ConfirmAction::BurnCompressed {
+            format!(
+                "Burn {} selected compressed NFT(s)?\n\nEach is burned through Bubblegum in its own transaction, with a proof from the DAS indexer.\nCompressed NFTs hold no rent, so no SOL is recovered.\n\nPress Y to confirm, N to cancel",
+                self.selected_count_for_confirmation
+            )
+        } else if self.confirm_external_destination {
+            format!(
+                "Rent from {} closed account(s) will be sent to\n\n{}\n\nThis account is not the signer. Make sure you control it.\n\nPress Y again to confirm, N to cancel",
+                self.selected_count_for_confirmation, self.rent_destination
+            )
+        } else if self.confirm_action == ConfirmAction::CloseMints {
+            format!(
+                "Close {} selected mint(s)?\n\nTheir supply is 0 and the signer is their MintCloseAuthority.\nThe mint addresses can never be used again once closed.\n\n{}:\n• Recover {} of rent to:\n{} {}\n\nPress Y to confirm, N to cancel",
+                self.selected_count_for_confirmation,
+                outcome,
+                format_sol(self.mints.selected().map(|mint| mint.lamports).sum()),
+                self.rent_destination,
+                destination_note
+            )
+        } else if self.confirm_action == ConfirmAction::CloseBuffers {
+            format!(
+                "Close {} selected program buffer(s)?\n\nA deploy still writing to one of them will fail.\n\n{}:\n• Recover {} to:\n{} {}\n\nPress Y to confirm, N to cancel",
+                self.selected_count_for_confirmation,
+                outcome,
+                format_sol(self.buffers.selected().map(|buffer| buffer.lamports).sum()),
+                self.rent_destination,
+                destination_note
+            )
+        } else {
+            let planner = self.planner();
+            let selected = || {
+                self.token_accounts
+                    .iter()
+                    .filter(|(_, selected)| *selected)
+                    .map(|(account, _)| account)
+            };
+            let closed = || selected().filter(|account| planner.closes(account));
+
+            // Accounts per action, in the order they first appear
+            let mut actions: Vec<(&str, usize)> = Vec::new();
+            for account in selected() {
+                let label = self.action_label(account);
+                match actions.iter_mut().find(|(other, _)| *other == label) {
+                    Some((_, count)) => *count += 1,
+                    None => actions.push((label, 1)),
+                }
+            }
+
+            let consolidated_count = selected()
+                .filter(|account| account.balance > 0 && planner.consolidates(account))
+                .count();
+            let transferred_count = selected()
+                .filter(|account| {
+                    account.action == AccountAction::TransferAndClose && account.balance > 0
+                })
+                .count();
+            let unwrapped_lamports = closed()
+                .filter(|account| {
+                    account.is_native() && account.action != AccountAction::TransferAndClose
+                })
+                .map(|account| account.balance)
+                .sum::<u64>();
+            let emptied_count = closed().filter(|a| a.needs_emptying).count();
+            let withheld_count = closed().filter(|a| a.withheld_amount > 0).count();
+            let burned_count = selected()
+                .filter(|account| {
+                    planner.burns(account)
+                        && !planner.consolidates(account)
+                        && !planner.burns_nft(account)
+                })
+                .count();
+            let nfts = || selected().filter(|account| planner.burns_nft(account));
+            let nft_count = nfts().count();
+            // Token Metadata closes the token account too and returns all rent to the owner
+            let nft_lamports = nfts()
+                .map(|account| account.lamports + account.nft.as_ref().map_or(0, |n| n.lamports))
+                .sum::<u64>();
+            let closed = || closed().filter(|account| !planner.burns_nft(account));
+            let closed_count = closed().count();
+            let rent_lamports = closed()
+                .map(|account| {
+                    if account.is_native() {
+                        account.lamports.saturating_sub(account.balance)
+                    } else {
+                        account.lamports
+                    }
+                })
+                .sum::<u64>();
+            let revoked_count = selected()
+                .filter(|account| account.action == AccountAction::RevokeOnly)
+                .count();
+
+            // One bullet per step, matching what the planner will emit
+            let mut steps = Vec::new();
+            if consolidated_count > 0 {
+                steps.push(format!(
+                    "• Move the balances of {} auxiliary account(s) into their canonical ATAs",
+                    consolidated_count
+                ));
+            }
+            if transferred_count > 0 {
+                steps.push(format!(
+                    "• Transfer the balances of {} account(s) to:\n{}",
+                    transferred_count,
+                    self.transfer_destination
+                        .map_or("(no transfer destination, press S)".to_string(), |pubkey| {
+                            pubkey.to_string()
+                        })
+                ));
+            }
+            if unwrapped_lamports > 0 {
+                steps.push(format!(
+                    "• Unwrap {} from WSOL accounts, sent with the rent (not burned)",
+                    format_sol(unwrapped_lamports)
+                ));
+            }
+            if emptied_count > 0 {
+                steps.push(format!(
+                    "• Empty the confidential balances of {} account(s) (one transaction each)",
+                    emptied_count
+                ));
+            }
+            if withheld_count > 0 {
+                steps.push(format!(
+                    "• Clear withheld transfer fees from {} account(s)",
+                    withheld_count
+                ));
+            }
+            if burned_count > 0 {
+                steps.push(format!("• Burn all tokens in {} account(s)", burned_count));
+            }
+            if nft_count > 0 {
+                steps.push(format!(
+                    "• Burn {} NFT(s) through Token Metadata, recovering {} of token, metadata and edition rent to the owner",
+                    nft_count,
+                    format_sol(nft_lamports)
+                ));
+            }
+            if revoked_count > 0 {
+                steps.push(format!(
+                    "• Revoke the delegates of {} account(s), keeping them open",
+                    revoked_count
+                ));
+            }
+            if closed_count > 0 {
+                steps.push(format!(
+                    "• Close {} token account(s)\n• Recover {} of rent to:\n{} {}",
+                    closed_count,
+                    format_sol(rent_lamports),
+                    self.rent_destination,
+                    destination_note
+                ));
+            } else if nft_count == 0 {
+                steps.push("• Keep the token accounts open (no rent recovered)".to_string());
+            }
+
+            format!(
+                "Are you sure you want to process {} selected account(s)?\n\n{}\n\n{}:\n{}\n\nPress Y to confirm, N to cancel",
+                self.selected_count_for_confirmation,
+                actions
+                    .iter()
+                    .map(|(label, count)| format!("{}: {}", label, count))
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                outcome,
+                steps.join("\n")
+            )
+        };
+        let height = message.lines().count() as u16 + 2;
+
         // Calculate the center area for the dialog
         let area = frame.area();
         let dialog_area = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Percentage(30),
-                Constraint::Length(8),
+                Constraint::Length(height),
                 Constraint::Percentage(30),
             ])
             .split(area)[1];
@@ -619,17 +2297,23 @@ impl App {
         // Clear the area
         frame.render_widget(Clear, dialog_area);
 
-        // Create the confirmation message
-        let message = format!(
-            "Are you sure you want to process {} selected account(s)?\n\nThis will:\n• Burn all tokens in selected accounts\n• Close the ATA accounts\n• Recover SOL rent\n\nPress Y to confirm, N to cancel",
-            self.selected_count_for_confirmation
-        );
-
         let dialog = Paragraph::new(message)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Confirm Processing")
+                    .title(if self.confirm_action == ConfirmAction::RevokeDelegates {
+                        "Confirm Revoke"
+                    } else if self.confirm_action == ConfirmAction::BurnCompressed {
+                        "Confirm Compressed Burn"
+                    } else if self.confirm_external_destination {
+                        "Confirm Rent Destination"
+                    } else if self.confirm_action == ConfirmAction::CloseMints {
+                        "Confirm Closing Mints"
+                    } else if self.confirm_action == ConfirmAction::CloseBuffers {
+                        "Confirm Closing Buffers"
+                    } else {
+                        "Confirm Processing"
+                    })
                     .border_style(Style::default().fg(Color::Red)),
             )
             .style(Style::default().fg(Color::White))
@@ -651,62 +2335,533 @@ impl App {
             return;
         }
 
-        let latest_blockhash = self.rpc_client.get_latest_blockhash().unwrap();
-        println!(
-            "Processing {} selected accounts...",
-            selected_accounts.len()
-        );
-        selected_accounts.chunks(12).for_each(|chunk| {
-            let ixs = chunk
-                .iter()
-                .flat_map(|account| {
-                    [
-                        self.create_burn_instruction(account)
-                            .unwrap_or_else(|e| panic!("Failed to create burn instruction: {e}")),
-                        self.create_close_ata_instruction(account)
-                            .unwrap_or_else(|e| {
-                                panic!("Failed to create close ATA instruction: {e}")
-                            }),
-                    ]
-                })
-                .collect::<Vec<_>>();
-            let tx = Transaction::new_signed_with_payer(
-                &ixs,
-                Some(&self.keypair.pubkey()),
-                &[&self.keypair],
-                latest_blockhash,
-            );
+        let processed = selected_accounts.len();
+        let batches = match self.plan_selected(selected_accounts) {
+            Ok(batches) => batches,
+            Err(message) => {
+                self.status_message = Some(message);
+                return;
+            }
+        };
+
+        if self.submit(batches) {
+            // Closed accounts are gone and balances moved, so list the accounts again
+            self.status_message = Some(match self.load_token_accounts() {
+                Ok(()) => {
+                    self.filter_accounts();
+                    format!("Processed {} account(s)", processed)
+                }
+                Err(e) => format!(
+                    "Processed {} account(s), but reloading them failed: {e}",
+                    processed
+                ),
+            });
+        }
+    }
+
+    /// Transactions carrying out the actions of `selected_accounts`, or why they cannot
+    /// be processed
+    fn plan_selected(
+        &self,
+        selected_accounts: Vec<TokenAccountInfo>,
+    ) -> Result<Vec<Vec<Instruction>>, String> {
+        let planner = self.planner();
+        if let Some(reason) = planner.refusal(&selected_accounts, &self.available_signers()) {
+            return Err(reason);
+        }
+
+        // Zero-knowledge proof verification is expensive, so each account being emptied gets
+        // its own transaction. The others fill transactions up to the size limit.
+        let (emptied, rest): (Vec<_>, Vec<_>) = selected_accounts
+            .into_iter()
+            .partition(|account| account.needs_emptying);
+        let plan = |account: &TokenAccountInfo| {
+            self.fetch_for_plan(account)
+                .and_then(|fetched| planner.plan_account(account, fetched))
+                .map_err(|e| format!("Failed to plan {}: {e}", account.address))
+        };
+        let groups = rest.iter().map(plan).collect::<Result<Vec<_>, _>>()?;
+        let mut batches = self
+            .pack(groups)
+            .map_err(|e| format!("Failed to plan transactions: {e}"))?;
+        for account in &emptied {
+            batches.push(plan(account)?);
+        }
+        Ok(batches)
+    }
+
+    /// Fetch what planning `account` needs from the chain: the state of its confidential
+    /// balances and the transfer fee configuration of its mint, when it is closed
+    fn fetch_for_plan(&self, account: &TokenAccountInfo) -> anyhow::Result<Fetched> {
+        use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+        use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+        let mut fetched = Fetched::default();
+        if !self.planner().closes(account) {
+            return Ok(fetched);
+        }
+        if account.needs_emptying {
+            // The confidential keys can only be derived from the owner's own keypair
+            let Some(owner) = self
+                .keypair
+                .as_deref()
+                .filter(|k| k.pubkey() == account.owner)
+            else {
+                anyhow::bail!("emptying confidential balances needs the owner's keypair");
+            };
+            let data = self.rpc_client.get_account_data(&account.address)?;
+            fetched.emptying = confidential::empty_instructions(
+                &account.program_id,
+                &account.address,
+                &data,
+                owner,
+            )?;
+        }
+        if account.withheld_amount > 0 {
+            let mint_data = self.rpc_client.get_account_data(&account.mint)?;
+            let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+            fetched.withdraw_withheld_authority = mint
+                .get_extension::<TransferFeeConfig>()
+                .ok()
+                .and_then(|config| Option::<Pubkey>::from(config.withdraw_withheld_authority));
+        }
+        Ok(fetched)
+    }
+
+    /// Revoke the delegates of the selected accounts without burning or closing them
+    fn revoke_selected(&mut self) {
+        let authority = self.authority();
+        let mut groups = Vec::new();
+        for (account, selected) in &self.token_accounts {
+            if !*selected || account.delegate.is_none() {
+                continue;
+            }
+            // Only the owner can revoke, and frozen accounts reject it
+            if account.owner != authority || account.frozen {
+                continue;
+            }
+            let revoke = self.planner().create_revoke_instruction(account);
+            match revoke {
+                Ok(ix) => groups.push(vec![ix]),
+                Err(e) => {
+                    self.status_message = Some(format!(
+                        "Failed to plan revoke for {}: {e}",
+                        account.address
+                    ));
+                    return;
+                }
+            }
+        }
+        if groups.is_empty() {
+            self.status_message =
+                Some("No selected delegate can be revoked (frozen or not owned)".to_string());
+            return;
+        }
+
+        let revoked = groups.len();
+        let batches = match self.pack(groups) {
+            Ok(batches) => batches,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to plan transactions: {e}"));
+                return;
+            }
+        };
+        if self.submit(batches) {
+            // Reflect the revocations without reloading every account
+            for (account, selected) in &mut self.token_accounts {
+                if *selected && account.owner == authority && !account.frozen {
+                    account.delegate = None;
+                    account.delegated_amount = 0;
+                }
+            }
+            self.sync_filtered_accounts();
+            self.status_message = Some(format!("Revoked {} delegate(s)", revoked));
+        }
+    }
+
+    /// Burn the selected compressed NFTs, one transaction each as proofs are large
+    fn burn_compressed_selected(&mut self) {
+        let Some(das) = &self.das else {
+            return;
+        };
+        let mut batches = Vec::new();
+        let mut trees = HashMap::new();
+        for asset in self.compressed_assets.selected() {
+            match self.plan_compressed_burn(das, asset, &mut trees) {
+                Ok(ix) => batches.push(vec![ix]),
+                Err(e) => {
+                    self.status_message = Some(format!("Failed to plan {}: {e}", asset.id));
+                    return;
+                }
+            }
+        }
+
+        let burned = batches.len();
+        if self.submit(batches) {
+            self.compressed_assets.remove_selected();
+            self.status_message = Some(format!("Burned {} compressed NFT(s)", burned));
+        }
+    }
+
+    /// Close the selected empty mints, returning their rent to the rent destination
+    fn close_selected_mints(&mut self) {
+        let authority = self.authority();
+        let signers = self.planner().signers_for(&authority);
+        let mut groups = Vec::new();
+        for mint in self.mints.selected() {
+            match close_account(
+                &spl_token_2022::id(),
+                &mint.address,
+                &self.rent_destination,
+                &authority,
+                &signers.iter().collect::<Vec<_>>(),
+            ) {
+                Ok(ix) => groups.push(vec![ix]),
+                Err(e) => {
+                    self.status_message =
+                        Some(format!("Failed to plan closing {}: {e}", mint.address));
+                    return;
+                }
+            }
+        }
+
+        let closed = groups.len();
+        let batches = match self.pack(groups) {
+            Ok(batches) => batches,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to plan transactions: {e}"));
+                return;
+            }
+        };
+        if self.submit(batches) {
+            self.mints.remove_selected();
+            self.status_message = Some(format!("Closed {} mint(s)", closed));
+        }
+    }
+
+    /// Close the selected program buffers, sending their lamports to the rent destination
+    fn close_selected_buffers(&mut self) {
+        let groups: Vec<Vec<Instruction>> = self
+            .buffers
+            .selected()
+            .map(|buffer| {
+                vec![solana_loader_v3_interface::instruction::close(
+                    &buffer.address,
+                    &self.rent_destination,
+                    &self.owner,
+                )]
+            })
+            .collect();
+
+        let closed = groups.len();
+        let batches = match self.pack(groups) {
+            Ok(batches) => batches,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to plan transactions: {e}"));
+                return;
+            }
+        };
+        if self.submit(batches) {
+            self.buffers.remove_selected();
+            self.status_message = Some(format!("Closed {} buffer(s)", closed));
+        }
+    }
+
+    /// Bubblegum burn of `asset` with its current proof. `trees` caches the canopy depth
+    /// and compression program of each tree account.
+    fn plan_compressed_burn(
+        &self,
+        das: &DasClient,
+        asset: &CompressedAsset,
+        trees: &mut HashMap<Pubkey, (usize, Pubkey)>,
+    ) -> Result<Instruction> {
+        let proof = das.asset_proof(&asset.id)?;
+        let (canopy_depth, compression_program) = match trees.get(&asset.tree) {
+            Some(tree) => *tree,
+            None => {
+                let account = self.rpc_client.get_account(&asset.tree)?;
+                let canopy_depth = bubblegum::canopy_depth(&account.data)
+                    .map_err(|e| color_eyre::eyre::eyre!("{e}"))?;
+                trees.insert(asset.tree, (canopy_depth, account.owner));
+                (canopy_depth, account.owner)
+            }
+        };
+        bubblegum::burn_instruction(asset, &proof, canopy_depth, &compression_program)
+            .map_err(|e| color_eyre::eyre::eyre!("{e}"))
+    }
+
+    /// Pack the instructions of each account into as few transactions as fit, counting
+    /// the fee payer's signature and key and the advance-nonce instruction, if any
+    fn pack(&self, groups: Vec<Vec<Instruction>>) -> Result<Vec<Vec<Instruction>>> {
+        let prefix = match self.nonce_accounts.first() {
+            Some(account) => {
+                vec![DurableNonce::fetch(&self.rpc_client, account)?.advance_instruction()]
+            }
+            None => Vec::new(),
+        };
+        batch::pack(groups, &self.fee_payer_pubkey(), &prefix)
+            .map_err(|e| color_eyre::eyre::eyre!("{e}"))
+    }
+
+    /// Sign and send one transaction per batch, or export them when signatures are missing.
+    /// Returns whether every transaction was sent.
+    fn submit(&mut self, mut batches: Vec<Vec<Instruction>>) -> bool {
+        let latest_blockhash = match self.rpc_client.get_latest_blockhash() {
+            Ok(blockhash) => blockhash,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to get a recent blockhash: {e}"));
+                return false;
+            }
+        };
+        let blockhashes = match self.attach_nonces(&mut batches, latest_blockhash) {
+            Ok(blockhashes) => blockhashes,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return false;
+            }
+        };
+
+        // Refuse to start if the fee payer cannot cover every transaction and the ATAs
+        // they create
+        let fee_payer = self.fee_payer_pubkey();
+        let estimated_fees = match self
+            .estimate_fees(&batches, &fee_payer, &latest_blockhash)
+            .and_then(|fees| Ok(fees + self.estimate_ata_rent(&batches)?))
+        {
+            Ok(fees) => fees,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to estimate fees: {e}"));
+                return false;
+            }
+        };
+
+        // Sign with every local key; anything still missing is handed over as a bundle
+        let transactions = match self.sign_locally(&batches, &fee_payer, &blockhashes) {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to sign transactions: {e}"));
+                return false;
+            }
+        };
+        if transactions.iter().any(|tx| !tx.is_signed()) {
+            // Only watch-only and multisig runs expect signatures from elsewhere
+            if self.keypair.is_some() && self.multisig.is_none() {
+                self.status_message = Some(
+                    "Transactions need signatures from keys not held locally, nothing was sent"
+                        .to_string(),
+                );
+            } else {
+                self.export_transactions(&transactions, estimated_fees);
+            }
+            return false;
+        }
+
+        if estimated_fees > self.fee_payer_lamports {
+            self.status_message = Some(format!(
+                "Fee payer {} has {} but {} transaction(s) need about {} in fees and rent",
+                fee_payer,
+                format_sol(self.fee_payer_lamports),
+                batches.len(),
+                format_sol(estimated_fees)
+            ));
+            return false;
+        }
+
+        let mut sent_all = true;
+        for (i, tx) in transactions.iter().enumerate() {
             // let tx = self.rpc_client.simulate_transaction(&tx).unwrap();
             // println!("tx: {:?}", tx);
-            self.rpc_client
-                .send_and_confirm_transaction_with_spinner(&tx)
-                .unwrap_or_else(|e| panic!("Failed to send transaction: {e}"));
-        });
+            if let Err(e) = self
+                .rpc_client
+                .send_and_confirm_transaction_with_spinner(tx)
+            {
+                self.status_message = Some(format!(
+                    "Transaction {} of {} failed, the rest were not sent: {e}",
+                    i + 1,
+                    transactions.len()
+                ));
+                sent_all = false;
+                break;
+            }
+        }
+
+        if let Err(e) = self.load_balances() {
+            self.status_message = Some(format!("Failed to refresh balances: {e}"));
+        }
+        sent_all
     }
 
-    fn create_burn_instruction(&self, account: &TokenAccountInfo) -> anyhow::Result<Instruction> {
-        spl_token::instruction::burn(
-            &spl_token::id(),
-            &account.address,
-            &account.mint,
-            &self.keypair.pubkey(),
-            &[&self.keypair.pubkey()],
-            account.balance,
-        )
-        .map_err(|e| e.into())
+    /// Prefix each batch with an advance-nonce instruction when durable nonces are
+    /// configured, and return the blockhash each batch's transaction must use
+    fn attach_nonces(
+        &self,
+        batches: &mut [Vec<Instruction>],
+        latest_blockhash: Hash,
+    ) -> Result<Vec<Hash>> {
+        if self.nonce_accounts.is_empty() {
+            return Ok(vec![latest_blockhash; batches.len()]);
+        }
+        // A nonce advances once per transaction, so batches cannot share one
+        if self.nonce_accounts.len() < batches.len() {
+            return Err(color_eyre::eyre::eyre!(
+                "{} transaction(s) need as many nonce accounts, only {} given",
+                batches.len(),
+                self.nonce_accounts.len()
+            ));
+        }
+
+        let fee_payer = self.fee_payer_pubkey();
+        let mut blockhashes = Vec::with_capacity(batches.len());
+        for (ixs, account) in batches.iter_mut().zip(&self.nonce_accounts) {
+            let nonce = DurableNonce::fetch(&self.rpc_client, account)?;
+            ixs.insert(0, nonce.advance_instruction());
+            // Batches were packed with room for the first nonce account's instruction,
+            // another authority may need more
+            if !batch::fits(ixs, &fee_payer) {
+                return Err(color_eyre::eyre::eyre!(
+                    "Nonce account {} has authority {}, which does not fit in the transaction; use nonce accounts with the same authority",
+                    account,
+                    nonce.authority
+                ));
+            }
+            blockhashes.push(nonce.blockhash);
+        }
+        Ok(blockhashes)
     }
 
-    fn create_close_ata_instruction(
+    /// Build one transaction per batch and add the signatures of every key held locally
+    /// (signer, fee payer and multisig cosigners) that the transaction requires
+    fn sign_locally(
         &self,
-        account: &TokenAccountInfo,
-    ) -> anyhow::Result<Instruction> {
-        close_account(
-            &spl_token::id(),
-            &account.address,
-            &self.keypair.pubkey(),
-            &self.keypair.pubkey(),
-            &[&self.keypair.pubkey()],
-        )
-        .map_err(|e| e.into())
+        batches: &[Vec<Instruction>],
+        fee_payer: &Pubkey,
+        blockhashes: &[Hash],
+    ) -> Result<Vec<Transaction>> {
+        let local_keys = self
+            .keypair
+            .iter()
+            .chain(&self.fee_payer)
+            .chain(&self.cosigners)
+            .map(|keypair| &**keypair)
+            .collect::<Vec<&Keypair>>();
+
+        let mut transactions = Vec::with_capacity(batches.len());
+        for (ixs, blockhash) in batches.iter().zip(blockhashes) {
+            let mut tx = Transaction::new_unsigned(Message::new_with_blockhash(
+                ixs,
+                Some(fee_payer),
+                blockhash,
+            ));
+            let required = tx.message.signer_keys();
+            let signers = local_keys
+                .iter()
+                .copied()
+                .filter(|keypair| required.contains(&&keypair.pubkey()))
+                .collect::<Vec<_>>();
+            tx.try_partial_sign(&signers, *blockhash)?;
+            transactions.push(tx);
+        }
+        Ok(transactions)
+    }
+
+    /// Write transactions still missing signatures to the export file
+    fn export_transactions(&mut self, transactions: &[Transaction], estimated_fees: u64) {
+        let result =
+            TransactionBundle::new(self.export_encoding, transactions).and_then(|bundle| {
+                bundle.write(&self.export_path)?;
+                Ok(bundle.required_signers)
+            });
+        self.status_message = Some(match result {
+            Ok(required_signers) => format!(
+                "Exported {} transaction(s) to {}, awaiting signatures from {} (fees and rent about {}{})",
+                transactions.len(),
+                self.export_path.display(),
+                required_signers.join(", "),
+                format_sol(estimated_fees),
+                if self.nonce_accounts.is_empty() {
+                    ", sign before the blockhash expires"
+                } else {
+                    ", using durable nonces"
+                }
+            ),
+            Err(e) => format!("Failed to export transactions: {e}"),
+        });
+    }
+
+    /// Sum of the network fees for sending each batch as one transaction
+    fn estimate_fees(
+        &self,
+        batches: &[Vec<Instruction>],
+        fee_payer: &Pubkey,
+        blockhash: &Hash,
+    ) -> Result<u64> {
+        let mut total = 0;
+        for ixs in batches {
+            let message = Message::new_with_blockhash(ixs, Some(fee_payer), blockhash);
+            total += self.rpc_client.get_fee_for_message(&message)?;
+        }
+        Ok(total)
+    }
+
+    /// Rent the fee payer puts into the associated token accounts that `batches` create
+    fn estimate_ata_rent(&self, batches: &[Vec<Instruction>]) -> Result<u64> {
+        use solana_sdk::program_pack::Pack;
+        use spl_token_2022::extension::{
+            BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+        };
+
+        // `CreateIdempotent` accounts: funder, ATA, wallet, mint, system and token program
+        let mut atas: Vec<(Pubkey, Pubkey, Pubkey)> = Vec::new();
+        for ix in batches.iter().flatten() {
+            if ix.program_id == spl_associated_token_account::id()
+                && ix.data == [1]
+                && !atas.iter().any(|(ata, ..)| *ata == ix.accounts[1].pubkey)
+            {
+                atas.push((
+                    ix.accounts[1].pubkey,
+                    ix.accounts[3].pubkey,
+                    ix.accounts[5].pubkey,
+                ));
+            }
+        }
+
+        let mut rent = 0;
+        let mut rent_by_len = HashMap::new();
+        for chunk in atas.chunks(100) {
+            let addresses: Vec<Pubkey> = chunk.iter().map(|(ata, ..)| *ata).collect();
+            let existing = self.rpc_client.get_multiple_accounts(&addresses)?;
+            for ((_, mint, program_id), account) in chunk.iter().zip(existing) {
+                if account.is_some() {
+                    continue;
+                }
+                // Token-2022 ATAs carry ImmutableOwner plus what the mint's extensions require
+                let len = if *program_id == spl_token_2022::id() {
+                    let data = self.rpc_client.get_account_data(mint)?;
+                    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+                    let mut types = ExtensionType::get_required_init_account_extensions(
+                        &mint.get_extension_types()?,
+                    );
+                    if !types.contains(&ExtensionType::ImmutableOwner) {
+                        types.push(ExtensionType::ImmutableOwner);
+                    }
+                    ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+                        &types,
+                    )?
+                } else {
+                    spl_token::state::Account::LEN
+                };
+                rent += match rent_by_len.get(&len) {
+                    Some(lamports) => *lamports,
+                    None => {
+                        let lamports = self
+                            .rpc_client
+                            .get_minimum_balance_for_rent_exemption(len)?;
+                        rent_by_len.insert(len, lamports);
+                        lamports
+                    }
+                };
+            }
+        }
+        Ok(rent)
     }
 }
@@ -0,0 +1,569 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+};
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Mint;
+
+use std::collections::HashMap;
+
+/// Metaplex Token Metadata program
+pub const PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Account keys stored in the first byte of Token Metadata accounts
+const KEY_METADATA_V1: u8 = 4;
+const KEY_MASTER_EDITION_V2: u8 = 6;
+
+/// `TokenStandard` values of programmable NFTs, whose token accounts stay frozen
+const PROGRAMMABLE_NON_FUNGIBLE: u8 = 4;
+const PROGRAMMABLE_NON_FUNGIBLE_EDITION: u8 = 5;
+
+/// `Burn` instruction, `BurnArgs::V1`
+const BURN_DISCRIMINATOR: u8 = 41;
+const BURN_ARGS_V1: u8 = 0;
+
+/// A token whose mint has Metaplex metadata, burned through Token Metadata so the
+/// metadata and edition accounts are closed along with the token account
+#[derive(Debug, Clone)]
+pub struct Nft {
+    pub metadata: Pubkey,
+    /// Master edition, absent for tokens minted without one
+    pub edition: Option<Pubkey>,
+    /// Metadata of the verified collection, whose size the burn decrements
+    pub collection_metadata: Option<Pubkey>,
+    /// Token record of a programmable NFT, tracking the state of its token account
+    pub token_record: Option<Pubkey>,
+    /// Lamports held by the metadata, edition and token record accounts, reclaimed by
+    /// the burn
+    pub lamports: u64,
+}
+
+impl Nft {
+    /// Programmable NFTs are frozen by Token Metadata and only move or burn through it
+    pub fn is_programmable(&self) -> bool {
+        self.token_record.is_some()
+    }
+}
+
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", PROGRAM_ID.as_ref(), mint.as_ref()],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn edition_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", PROGRAM_ID.as_ref(), mint.as_ref(), b"edition"],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn token_record_address(mint: &Pubkey, token: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"token_record",
+            token.as_ref(),
+        ],
+        &PROGRAM_ID,
+    )
+    .0
+}
+
+/// Find which `(mint, token account)` holdings are NFTs: decimals 0, supply 1 and a
+/// metadata account. Returned by token account address. Print editions are left out,
+/// burning them needs their master edition's accounts.
+pub fn load(rpc_client: &RpcClient, holdings: &[(Pubkey, Pubkey)]) -> Result<HashMap<Pubkey, Nft>> {
+    let mut nfts = HashMap::new();
+    // Four accounts per holding, within the 100 accounts of one getMultipleAccounts call
+    for chunk in holdings.chunks(25) {
+        let addresses: Vec<Pubkey> = chunk
+            .iter()
+            .flat_map(|(mint, token)| {
+                [
+                    *mint,
+                    metadata_address(mint),
+                    edition_address(mint),
+                    token_record_address(mint, token),
+                ]
+            })
+            .collect();
+        let accounts = rpc_client
+            .get_multiple_accounts(&addresses)
+            .map_err(|e| eyre!("Failed to load NFT metadata: {}", e))?;
+
+        for (i, (_, token)) in chunk.iter().enumerate() {
+            let range = i * 4..i * 4 + 4;
+            if let Some(nft) = nft_from_accounts(&addresses[range.clone()], &accounts[range]) {
+                nfts.insert(*token, nft);
+            }
+        }
+    }
+    Ok(nfts)
+}
+
+/// The NFT held by a token account, given the addresses and accounts of its mint,
+/// metadata, edition and token record, or `None` when it is not one `burn_instruction`
+/// can burn
+fn nft_from_accounts(addresses: &[Pubkey], accounts: &[Option<Account>]) -> Option<Nft> {
+    let [mint_account, metadata, edition, token_record] = accounts else {
+        return None;
+    };
+    let is_nft = mint_account.as_ref().is_some_and(|account| {
+        StateWithExtensions::<Mint>::unpack(&account.data)
+            .is_ok_and(|mint| mint.base.decimals == 0 && mint.base.supply == 1)
+    });
+    if !is_nft {
+        return None;
+    }
+    let metadata = metadata.as_ref().filter(|a| a.owner == PROGRAM_ID)?;
+    let parsed = parse_metadata(&metadata.data)?;
+
+    let edition = edition.as_ref().filter(|a| a.owner == PROGRAM_ID);
+    if edition
+        .and_then(|account| account.data.first())
+        .is_some_and(|key| *key != KEY_MASTER_EDITION_V2)
+    {
+        return None;
+    }
+
+    // Burning a programmable NFT closes its token record as well
+    let token_record = token_record
+        .as_ref()
+        .filter(|a| a.owner == PROGRAM_ID && parsed.programmable);
+    if parsed.programmable && token_record.is_none() {
+        return None;
+    }
+
+    Some(Nft {
+        metadata: addresses[1],
+        edition: edition.map(|_| addresses[2]),
+        collection_metadata: parsed.collection.map(|key| metadata_address(&key)),
+        token_record: token_record.map(|_| addresses[3]),
+        lamports: metadata.lamports
+            + edition.map_or(0, |a| a.lamports)
+            + token_record.map_or(0, |a| a.lamports),
+    })
+}
+
+/// Fields of a metadata account the burn depends on
+struct ParsedMetadata {
+    programmable: bool,
+    /// Verified collection mint
+    collection: Option<Pubkey>,
+}
+
+/// Borsh-decode the start of a `Metadata` account up to its collection. Accounts
+/// created before token standards existed end early; missing fields read as `None`.
+fn parse_metadata(data: &[u8]) -> Option<ParsedMetadata> {
+    let mut reader = Reader(data);
+    if reader.u8()? != KEY_METADATA_V1 {
+        return None;
+    }
+    reader.skip(32 + 32)?; // update authority, mint
+    for _ in 0..3 {
+        // name, symbol, uri
+        let len = reader.u32()? as usize;
+        reader.skip(len)?;
+    }
+    reader.skip(2)?; // seller fee basis points
+    if reader.u8()? == 1 {
+        let creators = reader.u32()? as usize;
+        reader.skip(creators * 34)?;
+    }
+    reader.skip(2)?; // primary sale happened, is mutable
+
+    // Fields added later are missing from old accounts
+    let _edition_nonce = reader.option(Reader::u8);
+    let token_standard = reader.option(Reader::u8).flatten();
+    let collection = reader
+        .option(|r| Some((r.u8()? == 1, r.pubkey()?)))
+        .flatten()
+        .and_then(|(verified, key)| verified.then_some(key));
+    Some(ParsedMetadata {
+        programmable: matches!(
+            token_standard,
+            Some(PROGRAMMABLE_NON_FUNGIBLE | PROGRAMMABLE_NON_FUNGIBLE_EDITION)
+        ),
+        collection,
+    })
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.take(32)
+            .map(|bytes| Pubkey::new_from_array(bytes.try_into().expect("32 bytes")))
+    }
+
+    /// A Borsh `Option<T>`: `None` when the data ends, `Some(None)` for a 0 tag
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        match self.u8()? {
+            0 => Some(None),
+            _ => read(self).map(Some),
+        }
+    }
+}
+
+/// Token Metadata `Burn` (v1) of `nft` held in `token`, closing the token, metadata and
+/// edition accounts, and the token record of a programmable NFT. Their rent goes to
+/// `authority`, the token owner.
+pub fn burn_instruction(
+    nft: &Nft,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    token: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    // Optional accounts that are absent are passed as the program id
+    let optional = |account: Option<Pubkey>| match account {
+        Some(pubkey) => AccountMeta::new(pubkey, false),
+        None => AccountMeta::new_readonly(PROGRAM_ID, false),
+    };
+    let accounts = vec![
+        AccountMeta::new(*authority, true),
+        optional(nft.collection_metadata),
+        AccountMeta::new(nft.metadata, false),
+        optional(nft.edition),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(*token, false),
+        optional(None), // master edition of a print
+        optional(None), // master edition mint
+        optional(None), // master edition token
+        optional(None), // edition marker
+        optional(nft.token_record),
+        AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        AccountMeta::new_readonly(solana_sdk::sysvar::instructions::ID, false),
+        AccountMeta::new_readonly(*token_program, false),
+    ];
+
+    let mut data = vec![BURN_DISCRIMINATOR, BURN_ARGS_V1];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::program_pack::Pack;
+
+    /// A `Metadata` account up to `is_mutable`, with `creators` creators
+    fn metadata(creators: u32) -> Vec<u8> {
+        let mut data = vec![KEY_METADATA_V1];
+        data.extend_from_slice(&[0; 64]); // update authority, mint
+        for field in ["Name", "SYM", "https://example.com/nft.json"] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(&500u16.to_le_bytes()); // seller fee basis points
+        if creators == 0 {
+            data.push(0);
+        } else {
+            data.push(1);
+            data.extend_from_slice(&creators.to_le_bytes());
+            for _ in 0..creators {
+                data.extend_from_slice(&[7; 32]); // address
+                data.extend_from_slice(&[1, 100 / creators as u8]); // verified, share
+            }
+        }
+        data.extend_from_slice(&[0, 1]); // primary sale happened, is mutable
+        data
+    }
+
+    /// `metadata` followed by an edition nonce, token standard and collection
+    fn metadata_v1_3(token_standard: u8, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+        let mut data = metadata(2);
+        data.extend_from_slice(&[1, 255]); // edition nonce
+        data.extend_from_slice(&[1, token_standard]);
+        match collection {
+            Some((verified, key)) => {
+                data.extend_from_slice(&[1, verified as u8]);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.push(0),
+        }
+        // Uses, collection details and padding follow on chain
+        data.extend_from_slice(&[0; 40]);
+        data
+    }
+
+    #[test]
+    fn creators_are_skipped() {
+        let collection = Pubkey::new_unique();
+        for creators in [0, 1, 3] {
+            let mut data = metadata(creators);
+            data.extend_from_slice(&[0, 1, 0, 1, 1]);
+            data.extend_from_slice(collection.as_ref());
+            let parsed = parse_metadata(&data).unwrap();
+            assert!(!parsed.programmable);
+            assert_eq!(parsed.collection, Some(collection));
+        }
+    }
+
+    #[test]
+    fn old_accounts_end_before_the_token_standard() {
+        let mut data = metadata(1);
+        let parsed = parse_metadata(&data).unwrap();
+        assert!(!parsed.programmable);
+        assert_eq!(parsed.collection, None);
+
+        data.extend_from_slice(&[1, 254]); // edition nonce only
+        let parsed = parse_metadata(&data).unwrap();
+        assert!(!parsed.programmable);
+        assert_eq!(parsed.collection, None);
+    }
+
+    #[test]
+    fn only_verified_collections_are_kept() {
+        let collection = Pubkey::new_unique();
+        let verified = parse_metadata(&metadata_v1_3(0, Some((true, collection)))).unwrap();
+        assert_eq!(verified.collection, Some(collection));
+        let unverified = parse_metadata(&metadata_v1_3(0, Some((false, collection)))).unwrap();
+        assert_eq!(unverified.collection, None);
+        let absent = parse_metadata(&metadata_v1_3(0, None)).unwrap();
+        assert_eq!(absent.collection, None);
+    }
+
+    #[test]
+    fn other_accounts_are_not_metadata() {
+        let mut data = metadata_v1_3(0, None);
+        data[0] = KEY_MASTER_EDITION_V2;
+        assert!(parse_metadata(&data).is_none());
+        // Truncated inside the name
+        assert!(parse_metadata(&metadata(0)[..70]).is_none());
+        assert!(parse_metadata(&[]).is_none());
+    }
+
+    /// A mint account with `decimals` and `supply`
+    fn mint(decimals: u8, supply: u64) -> Account {
+        let mut data = vec![0; Mint::LEN];
+        let state = Mint {
+            supply,
+            decimals,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        Mint::pack(state, &mut data).unwrap();
+        Account {
+            data,
+            ..Account::default()
+        }
+    }
+
+    /// An account of the Token Metadata program holding `data`
+    fn owned(data: Vec<u8>, lamports: u64) -> Account {
+        Account {
+            lamports,
+            data,
+            owner: PROGRAM_ID,
+            ..Account::default()
+        }
+    }
+
+    fn holding_addresses() -> Vec<Pubkey> {
+        (0..4).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    #[test]
+    fn nfts_are_read_from_their_accounts() {
+        let addresses = holding_addresses();
+        let collection = Pubkey::new_unique();
+        let accounts = [
+            Some(mint(0, 1)),
+            Some(owned(metadata_v1_3(0, Some((true, collection))), 5)),
+            Some(owned(vec![KEY_MASTER_EDITION_V2, 0, 0], 3)),
+            None,
+        ];
+        let nft = nft_from_accounts(&addresses, &accounts).unwrap();
+        assert_eq!(nft.metadata, addresses[1]);
+        assert_eq!(nft.edition, Some(addresses[2]));
+        assert_eq!(nft.collection_metadata, Some(metadata_address(&collection)));
+        assert_eq!(nft.token_record, None);
+        assert_eq!(nft.lamports, 8);
+
+        // Minted without a master edition
+        let accounts = [accounts[0].clone(), accounts[1].clone(), None, None];
+        let nft = nft_from_accounts(&addresses, &accounts).unwrap();
+        assert_eq!(nft.edition, None);
+        assert_eq!(nft.lamports, 5);
+    }
+
+    #[test]
+    fn prints_and_fungible_tokens_are_skipped() {
+        let addresses = holding_addresses();
+        let metadata = || Some(owned(metadata_v1_3(0, None), 5));
+        // Print editions need accounts of their master edition to burn
+        let print = [Some(mint(0, 1)), metadata(), Some(owned(vec![1], 3)), None];
+        assert!(nft_from_accounts(&addresses, &print).is_none());
+        for (decimals, supply) in [(6, 1), (0, 2)] {
+            let accounts = [Some(mint(decimals, supply)), metadata(), None, None];
+            assert!(nft_from_accounts(&addresses, &accounts).is_none());
+        }
+        // Metadata must belong to Token Metadata
+        let mut foreign = owned(metadata_v1_3(0, None), 5);
+        foreign.owner = Pubkey::new_unique();
+        let accounts = [Some(mint(0, 1)), Some(foreign), None, None];
+        assert!(nft_from_accounts(&addresses, &accounts).is_none());
+        assert!(nft_from_accounts(&addresses, &[None, metadata(), None, None]).is_none());
+    }
+
+    #[test]
+    fn programmable_token_standards() {
+        for (token_standard, programmable) in
+            [(0, false), (1, false), (3, false), (4, true), (5, true)]
+        {
+            let parsed = parse_metadata(&metadata_v1_3(token_standard, None)).unwrap();
+            assert_eq!(
+                parsed.programmable, programmable,
+                "token standard {token_standard}"
+            );
+        }
+    }
+
+    #[test]
+    fn programmable_nfts_need_their_token_record() {
+        let addresses = holding_addresses();
+        let mint_account = || Some(mint(0, 1));
+        let metadata = || Some(owned(metadata_v1_3(PROGRAMMABLE_NON_FUNGIBLE, None), 5));
+        let edition = || Some(owned(vec![KEY_MASTER_EDITION_V2], 3));
+
+        let accounts = [
+            mint_account(),
+            metadata(),
+            edition(),
+            Some(owned(vec![0; 80], 2)),
+        ];
+        let nft = nft_from_accounts(&addresses, &accounts).unwrap();
+        assert!(nft.is_programmable());
+        assert_eq!(nft.token_record, Some(addresses[3]));
+        assert_eq!(nft.lamports, 10);
+
+        // Without it the burn would fail, so the holding is left alone
+        let accounts = [mint_account(), metadata(), edition(), None];
+        assert!(nft_from_accounts(&addresses, &accounts).is_none());
+        let mut foreign = owned(vec![0; 80], 2);
+        foreign.owner = Pubkey::new_unique();
+        let accounts = [mint_account(), metadata(), edition(), Some(foreign)];
+        assert!(nft_from_accounts(&addresses, &accounts).is_none());
+
+        // A record left over for a regular NFT is not passed to the burn
+        let accounts = [
+            mint_account(),
+            Some(owned(metadata_v1_3(0, None), 5)),
+            edition(),
+            Some(owned(vec![0; 80], 2)),
+        ];
+        let nft = nft_from_accounts(&addresses, &accounts).unwrap();
+        assert_eq!(nft.token_record, None);
+        assert_eq!(nft.lamports, 8);
+    }
+
+    #[test]
+    fn token_record_is_passed_to_the_burn() {
+        let mint = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let nft = Nft {
+            metadata: metadata_address(&mint),
+            edition: Some(edition_address(&mint)),
+            collection_metadata: None,
+            token_record: Some(token_record_address(&mint, &token)),
+            lamports: 0,
+        };
+        let ix = burn_instruction(&nft, &Pubkey::new_unique(), &mint, &token, &spl_token::id());
+        assert_eq!(ix.accounts.len(), 14);
+        assert_eq!(
+            ix.accounts[10],
+            AccountMeta::new(token_record_address(&mint, &token), false)
+        );
+        // The print edition accounts before it stay placeholders
+        for meta in &ix.accounts[6..10] {
+            assert_eq!(*meta, AccountMeta::new_readonly(PROGRAM_ID, false));
+        }
+    }
+
+    #[test]
+    fn burn_instruction_layout() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+        let nft = Nft {
+            metadata: metadata_address(&mint),
+            edition: Some(edition_address(&mint)),
+            collection_metadata: Some(metadata_address(&collection)),
+            token_record: None,
+            lamports: 0,
+        };
+        let authority = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let ix = burn_instruction(&nft, &authority, &mint, &token, &spl_token::id());
+
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        assert_eq!(ix.data, [41, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        let absent = AccountMeta::new_readonly(PROGRAM_ID, false);
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(authority, true),
+                AccountMeta::new(metadata_address(&collection), false),
+                AccountMeta::new(metadata_address(&mint), false),
+                AccountMeta::new(edition_address(&mint), false),
+                AccountMeta::new(mint, false),
+                AccountMeta::new(token, false),
+                absent.clone(),
+                absent.clone(),
+                absent.clone(),
+                absent.clone(),
+                absent.clone(),
+                AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::instructions::ID, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ]
+        );
+
+        // Without a collection or edition those are passed as the program id too
+        let nft = Nft {
+            edition: None,
+            collection_metadata: None,
+            ..nft
+        };
+        let ix = burn_instruction(&nft, &authority, &mint, &token, &spl_token::id());
+        assert_eq!(ix.accounts[1], absent);
+        assert_eq!(ix.accounts[3], absent);
+    }
+}
@@ -0,0 +1,69 @@
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Multisig;
+
+/// An SPL Token multisig acting as the owner or close authority of token accounts,
+/// together with the members chosen to sign on its behalf
+pub struct MultisigAuthority {
+    pub account: Pubkey,
+    /// Required number of signatures (M)
+    pub threshold: u8,
+    /// Number of members (N)
+    pub members: u8,
+    /// The M members whose signatures go into every instruction
+    pub signers: Vec<Pubkey>,
+}
+
+impl MultisigAuthority {
+    /// Load the multisig at `account` and pick M signers, preferring members whose keys
+    /// are `local` so as few signatures as possible have to be collected afterwards
+    pub fn load(
+        rpc_client: &RpcClient,
+        account: &Pubkey,
+        local: &[Pubkey],
+        remote: &[Pubkey],
+    ) -> Result<Self> {
+        let data = rpc_client
+            .get_account_data(account)
+            .map_err(|e| eyre!("Failed to load multisig {}: {}", account, e))?;
+        let multisig = Multisig::unpack(&data)
+            .map_err(|e| eyre!("{} is not an SPL Token multisig: {}", account, e))?;
+        let members = &multisig.signers[..multisig.n as usize];
+
+        for pubkey in remote {
+            if !members.contains(pubkey) {
+                return Err(eyre!("{} is not a member of multisig {}", pubkey, account));
+            }
+        }
+
+        let mut signers = Vec::with_capacity(multisig.m as usize);
+        for pubkey in local.iter().filter(|p| members.contains(p)).chain(remote) {
+            if signers.len() < multisig.m as usize && !signers.contains(pubkey) {
+                signers.push(*pubkey);
+            }
+        }
+        if signers.len() < multisig.m as usize {
+            return Err(eyre!(
+                "Multisig {} needs {} of its {} members to sign, only {} given (members: {})",
+                account,
+                multisig.m,
+                multisig.n,
+                signers.len(),
+                members
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        Ok(Self {
+            account: *account,
+            threshold: multisig.m,
+            members: multisig.n,
+            signers,
+        })
+    }
+}
@@ -0,0 +1,322 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+use std::path::Path;
+
+/// Text encoding of serialized transactions in a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxEncoding {
+    Base58,
+    Base64,
+}
+
+impl TxEncoding {
+    /// Serialize a transaction, including any missing (all-zero) signatures
+    pub fn encode(self, tx: &Transaction) -> Result<String> {
+        let bytes = bincode::serialize(tx)?;
+        Ok(match self {
+            TxEncoding::Base58 => bs58::encode(bytes).into_string(),
+            TxEncoding::Base64 => BASE64.encode(bytes),
+        })
+    }
+
+    pub fn decode(self, encoded: &str) -> Result<Transaction> {
+        let bytes = match self {
+            TxEncoding::Base58 => bs58::decode(encoded.trim()).into_vec()?,
+            TxEncoding::Base64 => BASE64.decode(encoded.trim())?,
+        };
+        Ok(bincode::deserialize(&bytes)?)
+    }
+}
+
+/// Transactions planned on one machine and handed over to another, stored as JSON
+#[derive(Serialize, Deserialize)]
+pub struct TransactionBundle {
+    pub encoding: TxEncoding,
+    /// Pubkeys whose signatures are still missing
+    pub required_signers: Vec<String>,
+    pub transactions: Vec<String>,
+}
+
+impl TransactionBundle {
+    pub fn new(encoding: TxEncoding, transactions: &[Transaction]) -> Result<Self> {
+        let mut required_signers = Vec::new();
+        for tx in transactions {
+            for pubkey in missing_signers(tx) {
+                let pubkey = pubkey.to_string();
+                if !required_signers.contains(&pubkey) {
+                    required_signers.push(pubkey);
+                }
+            }
+        }
+
+        Ok(Self {
+            encoding,
+            required_signers,
+            transactions: transactions
+                .iter()
+                .map(|tx| encoding.encode(tx))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| eyre!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| eyre!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn decode(&self) -> Result<Vec<Transaction>> {
+        self.transactions
+            .iter()
+            .map(|tx| self.encoding.decode(tx))
+            .collect()
+    }
+}
+
+/// A durable nonce account and the blockhash it currently stores
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+}
+
+impl DurableNonce {
+    pub fn fetch(rpc_client: &RpcClient, account: &Pubkey) -> Result<Self> {
+        let data = solana_rpc_client_nonce_utils::get_account(rpc_client, account)
+            .and_then(|nonce| solana_rpc_client_nonce_utils::data_from_account(&nonce))
+            .map_err(|e| eyre!("Failed to load nonce account {}: {}", account, e))?;
+        Ok(Self {
+            account: *account,
+            authority: data.authority,
+            blockhash: data.blockhash(),
+        })
+    }
+
+    /// Instruction that must come first in a transaction using this nonce
+    pub fn advance_instruction(&self) -> Instruction {
+        solana_system_interface::instruction::advance_nonce_account(&self.account, &self.authority)
+    }
+}
+
+/// Add `keypair`'s signature to every transaction in the bundle at `path` that needs it.
+/// Runs fully offline; other signers can sign the same bundle afterwards.
+pub fn sign_bundle(path: &Path, output: &Path, keypair: &Keypair) -> Result<()> {
+    let bundle = TransactionBundle::read(path)?;
+    let mut transactions = bundle.decode()?;
+    let pubkey = keypair.pubkey();
+
+    let mut signed = 0;
+    for (i, tx) in transactions.iter_mut().enumerate() {
+        if !missing_signers(tx).contains(&pubkey) {
+            continue;
+        }
+        println!(
+            "Transaction {}: {} instruction(s) for programs {}",
+            i,
+            tx.message.instructions.len(),
+            program_ids(tx).join(", ")
+        );
+        let blockhash = tx.message.recent_blockhash;
+        tx.try_partial_sign(&[keypair], blockhash)
+            .map_err(|e| eyre!("Failed to sign transaction {}: {}", i, e))?;
+        signed += 1;
+    }
+    if signed == 0 {
+        return Err(eyre!(
+            "{} is not a missing signer of any transaction in {}",
+            pubkey,
+            path.display()
+        ));
+    }
+
+    TransactionBundle::new(bundle.encoding, &transactions)?.write(output)?;
+    println!(
+        "Signed {} of {} transaction(s) as {}, written to {}",
+        signed,
+        transactions.len(),
+        pubkey,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Send every fully signed transaction of the bundle at `path`
+pub fn broadcast_bundle(path: &Path, rpc_client: &RpcClient) -> Result<()> {
+    let transactions = TransactionBundle::read(path)?.decode()?;
+
+    // Refuse to send anything until every transaction is complete
+    for (i, tx) in transactions.iter().enumerate() {
+        let missing = missing_signers(tx);
+        if !missing.is_empty() {
+            let missing = missing.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            return Err(eyre!(
+                "Transaction {} is missing signatures from {}",
+                i,
+                missing.join(", ")
+            ));
+        }
+    }
+
+    let mut failed = 0;
+    for (i, tx) in transactions.iter().enumerate() {
+        match rpc_client.send_and_confirm_transaction_with_spinner(tx) {
+            Ok(signature) => println!("Transaction {}: {}", i, signature),
+            Err(e) => {
+                eprintln!("Transaction {} failed: {}", i, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(eyre!(
+            "{} of {} transaction(s) failed",
+            failed,
+            transactions.len()
+        ));
+    }
+    Ok(())
+}
+
+fn program_ids(tx: &Transaction) -> Vec<String> {
+    let mut programs = Vec::new();
+    for ix in &tx.message.instructions {
+        let program = tx.message.account_keys[ix.program_id_index as usize].to_string();
+        if !programs.contains(&program) {
+            programs.push(program);
+        }
+    }
+    programs
+}
+
+/// Signer pubkeys of `tx` whose signature slot is still empty
+fn missing_signers(tx: &Transaction) -> Vec<Pubkey> {
+    let num_signers = tx.message.header.num_required_signatures as usize;
+    tx.message.account_keys[..num_signers]
+        .iter()
+        .zip(&tx.signatures)
+        .filter(|(_, signature)| **signature == Default::default())
+        .map(|(pubkey, _)| *pubkey)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::message::Message;
+
+    /// A transaction paid by `payer` that `other` must sign as well, with no signatures yet
+    fn two_signer_transaction(payer: &Pubkey, other: &Pubkey) -> Transaction {
+        let recipient = Pubkey::new_unique();
+        let ixs = [
+            solana_system_interface::instruction::transfer(payer, &recipient, 1),
+            solana_system_interface::instruction::transfer(other, &recipient, 1),
+        ];
+        Transaction::new_unsigned(Message::new_with_blockhash(
+            &ixs,
+            Some(payer),
+            &Hash::new_unique(),
+        ))
+    }
+
+    /// A path in the temp directory unique to this test run
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.json", name, Pubkey::new_unique()))
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let payer = Keypair::new();
+        let mut tx = two_signer_transaction(&payer.pubkey(), &Pubkey::new_unique());
+        let blockhash = tx.message.recent_blockhash;
+        tx.try_partial_sign(&[&payer], blockhash).unwrap();
+
+        for encoding in [TxEncoding::Base58, TxEncoding::Base64] {
+            let encoded = encoding.encode(&tx).unwrap();
+            let decoded = encoding.decode(&format!("{encoded}\n")).unwrap();
+            assert_eq!(decoded, tx);
+        }
+        // The encodings are not interchangeable
+        let base64 = TxEncoding::Base64.encode(&tx).unwrap();
+        assert!(TxEncoding::Base58.decode(&base64).is_err());
+    }
+
+    #[test]
+    fn required_signers_shrink_after_signing() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let transactions = [
+            two_signer_transaction(&payer.pubkey(), &other.pubkey()),
+            two_signer_transaction(&payer.pubkey(), &other.pubkey()),
+        ];
+        let bundle = TransactionBundle::new(TxEncoding::Base64, &transactions).unwrap();
+        assert_eq!(
+            bundle.required_signers,
+            [payer.pubkey().to_string(), other.pubkey().to_string()]
+        );
+
+        let path = temp_path("bundle");
+        let signed_path = temp_path("signed");
+        bundle.write(&path).unwrap();
+        sign_bundle(&path, &signed_path, &other).unwrap();
+        let signed = TransactionBundle::read(&signed_path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&signed_path).unwrap();
+
+        assert_eq!(signed.encoding, TxEncoding::Base64);
+        assert_eq!(signed.required_signers, [payer.pubkey().to_string()]);
+        for tx in signed.decode().unwrap() {
+            assert_eq!(missing_signers(&tx), [payer.pubkey()]);
+            // The added signature is valid for the message
+            let index = tx
+                .message
+                .account_keys
+                .iter()
+                .position(|key| *key == other.pubkey())
+                .unwrap();
+            assert!(tx.signatures[index].verify(other.pubkey().as_ref(), &tx.message_data()));
+        }
+    }
+
+    #[test]
+    fn sign_bundle_refuses_other_keys() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let tx = two_signer_transaction(&payer.pubkey(), &other.pubkey());
+        let path = temp_path("bundle");
+        TransactionBundle::new(TxEncoding::Base58, &[tx])
+            .unwrap()
+            .write(&path)
+            .unwrap();
+
+        let output = temp_path("signed");
+        let result = sign_bundle(&path, &output, &Keypair::new());
+        assert!(result.is_err());
+        assert!(!output.exists());
+
+        // A signer whose signature is already present is refused too
+        sign_bundle(&path, &path, &payer).unwrap();
+        let result = sign_bundle(&path, &output, &payer);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert!(!output.exists());
+    }
+}
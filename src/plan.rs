@@ -0,0 +1,919 @@
+use anyhow::{anyhow, ensure};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::instruction::close_account;
+
+use crate::multisig::MultisigAuthority;
+use crate::{AccountAction, TokenAccountInfo, metaplex};
+
+/// Decides what processing does with each token account, from the listed state alone.
+/// Everything it needs from the chain is fetched beforehand into [`Fetched`].
+pub struct Planner<'a> {
+    /// The signer, which must own NFTs burned through Token Metadata
+    pub owner: Pubkey,
+    /// The key owning the listed accounts: the multisig if one is given, else the owner
+    pub authority: Pubkey,
+    pub multisig: Option<&'a MultisigAuthority>,
+    /// Funds the ATAs transfers create
+    pub fee_payer: Pubkey,
+    pub rent_destination: Pubkey,
+    pub transfer_destination: Option<Pubkey>,
+    /// Global mode, for accounts left on the default action
+    pub burn_token: bool,
+    pub close_ata: bool,
+    pub consolidate: bool,
+    /// Every listed account, to look up the ATAs balances move into
+    pub token_accounts: &'a [(TokenAccountInfo, bool)],
+}
+
+/// Chain state needed to plan an account that is closed
+#[derive(Default)]
+pub struct Fetched {
+    /// Instructions emptying its confidential balances
+    pub emptying: Vec<Instruction>,
+    /// Withdraw authority of the transfer fees withheld in it
+    pub withdraw_withheld_authority: Option<Pubkey>,
+}
+
+impl Planner<'_> {
+    /// Whether processing burns the balance of `account`
+    pub fn burns(&self, account: &TokenAccountInfo) -> bool {
+        match account.action {
+            AccountAction::Default => self.burn_token,
+            AccountAction::BurnAndClose => true,
+            _ => false,
+        }
+    }
+
+    /// Whether processing closes `account`
+    pub fn closes(&self, account: &TokenAccountInfo) -> bool {
+        match account.action {
+            AccountAction::Default => self.close_ata,
+            AccountAction::RevokeOnly => false,
+            _ => true,
+        }
+    }
+
+    /// Whether `account` holds an NFT burned through Token Metadata, which also closes its
+    /// metadata and edition accounts. The owner must sign, so multisigs burn plainly.
+    pub fn burns_nft(&self, account: &TokenAccountInfo) -> bool {
+        account.nft.is_some()
+            && account.owner == self.owner
+            && account.balance == 1
+            && account.withheld_amount == 0
+            && !account.needs_emptying
+            && self.burns(account)
+            && self.closes(account)
+            && !self.consolidates(account)
+    }
+
+    /// Whether the balance of `account` is moved to the canonical ATA instead of burned.
+    /// Only worth it when the account is closed afterwards.
+    pub fn consolidates(&self, account: &TokenAccountInfo) -> bool {
+        self.consolidate
+            && self.closes(account)
+            && account.action != AccountAction::TransferAndClose
+            && !account.canonical
+            && !account.is_native()
+    }
+
+    /// Multisig members to pass to a token instruction whose authority is `authority`.
+    /// Empty for a single key, which signs as the authority itself.
+    pub fn signers_for(&self, authority: &Pubkey) -> Vec<Pubkey> {
+        match self.multisig {
+            Some(multisig) if multisig.account == *authority => multisig.signers.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Why the `selected` accounts cannot be processed together, if they cannot.
+    /// `signers` are the keys that can sign here or through the export file.
+    pub fn refusal(&self, selected: &[TokenAccountInfo], signers: &[Pubkey]) -> Option<String> {
+        if let Some((account, reason)) = selected.iter().find_map(|account| {
+            Some((
+                account,
+                account.blocked_reason(self.closes(account), signers)?,
+            ))
+        }) {
+            return Some(format!(
+                "{} cannot be processed because {}",
+                account.address, reason
+            ));
+        }
+
+        // Balances cannot be moved into an ATA that is closed in the same run
+        let closed_ata = |owner: &Pubkey, account: &TokenAccountInfo| {
+            let ata = get_associated_token_address_with_program_id(
+                owner,
+                &account.mint,
+                &account.program_id,
+            );
+            selected
+                .iter()
+                .any(|other| other.address == ata && self.closes(other))
+        };
+        if let Some(account) = selected.iter().find(|account| {
+            self.consolidates(account) && account.balance > 0 && closed_ata(&account.owner, account)
+        }) {
+            return Some(format!(
+                "The canonical ATA for mint {} is selected too; deselect it to consolidate into it",
+                account.mint
+            ));
+        }
+
+        let refused = selected.iter().find_map(|account| {
+            let reason = match account.action {
+                // Token Metadata keeps pNFTs frozen; its burn is the only way to close them
+                _ if account.is_programmable() && !self.burns_nft(account) => {
+                    "programmable NFTs can only be burned and closed through Token Metadata"
+                        .to_string()
+                }
+                AccountAction::RevokeOnly if account.delegate.is_none() => {
+                    "it has no delegate to revoke".to_string()
+                }
+                AccountAction::RevokeOnly if account.owner != self.authority => {
+                    "only its owner can revoke the delegate".to_string()
+                }
+                AccountAction::TransferAndClose if account.balance > 0 => {
+                    match self.transfer_destination {
+                        None => "no transfer destination is set, press S".to_string(),
+                        Some(recipient) if closed_ata(&recipient, account) => {
+                            "the recipient's token account is being closed too".to_string()
+                        }
+                        Some(_) => return None,
+                    }
+                }
+                // Close-only never destroys tokens, burn-only needs accounts we can burn from
+                _ if !self.burns(account)
+                    && self.closes(account)
+                    && account.action != AccountAction::TransferAndClose
+                    && account.balance > 0
+                    && !account.is_native()
+                    && !self.consolidates(account) =>
+                {
+                    format!(
+                        "it holds {} tokens and close-only only closes empty accounts",
+                        account.ui_balance
+                    )
+                }
+                _ if self.burns(account)
+                    && !self.closes(account)
+                    && (account.is_native() || account.owner != self.authority) =>
+                {
+                    "burn-only skips WSOL and accounts we do not own".to_string()
+                }
+                _ => return None,
+            };
+            Some(format!(
+                "{} cannot be processed: {}",
+                account.address, reason
+            ))
+        });
+        if refused.is_some() {
+            return refused;
+        }
+
+        // Accounts we only hold the close authority of cannot be burned from
+        selected
+            .iter()
+            .find(|account| {
+                self.closes(account)
+                    && account.owner != self.authority
+                    && account.balance > 0
+                    && !account.is_native()
+            })
+            .map(|account| {
+                format!(
+                    "{} still holds tokens and is owned by {}, which must empty it before it can be closed",
+                    account.address, account.owner
+                )
+            })
+    }
+
+    /// Instructions carrying out the action of one account
+    pub fn plan_account(
+        &self,
+        account: &TokenAccountInfo,
+        fetched: Fetched,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        if account.action == AccountAction::RevokeOnly {
+            return Ok(vec![self.create_revoke_instruction(account)?]);
+        }
+        if let Some(nft) = account.nft.as_ref().filter(|_| self.burns_nft(account)) {
+            // Token Metadata burns and closes through CPI, which CpiGuard rejects
+            ensure!(
+                !account.cpi_guard,
+                "CpiGuard blocks the Token Metadata burn, disable it first"
+            );
+            return Ok(vec![metaplex::burn_instruction(
+                nft,
+                &account.owner,
+                &account.mint,
+                &account.address,
+                &account.program_id,
+            )]);
+        }
+        let close = self.closes(account);
+        let transfer = account.action == AccountAction::TransferAndClose;
+
+        let mut ixs = Vec::new();
+        if close && account.needs_emptying {
+            ixs.extend(fetched.emptying);
+        }
+        let mut amount = account.balance;
+        if close && account.withheld_amount > 0 {
+            let (ix, credited) = self
+                .create_withheld_fees_instruction(account, fetched.withdraw_withheld_authority)?;
+            ixs.push(ix);
+            amount += credited;
+            ensure!(
+                self.burns(account) || amount == 0 || transfer || self.consolidates(account),
+                "withdrawing its withheld fees credits tokens that close-only mode cannot burn"
+            );
+        }
+        // Accounts we only hold the close authority of are already empty
+        if account.owner == self.authority {
+            if transfer && amount > 0 {
+                let recipient = self
+                    .transfer_destination
+                    .ok_or_else(|| anyhow!("no transfer destination is set"))?;
+                ixs.extend(self.create_transfer_instructions(account, amount, &recipient)?);
+            } else if account.is_native() {
+                // Closing returns the wrapped lamports
+            } else if self.consolidates(account) && amount > 0 {
+                ixs.extend(self.create_transfer_instructions(account, amount, &account.owner)?);
+            } else if self.burns(account) {
+                ixs.push(self.create_burn_instruction(account, amount)?);
+            }
+        }
+        if close {
+            ixs.push(self.create_close_ata_instruction(account)?);
+        }
+        Ok(ixs)
+    }
+
+    /// Move `amount` from `account` into the ATA of `recipient`, creating the ATA if
+    /// needed, so the account can be closed without burning. With the owner as
+    /// recipient this consolidates an auxiliary account into its canonical ATA.
+    fn create_transfer_instructions(
+        &self,
+        account: &TokenAccountInfo,
+        amount: u64,
+        recipient: &Pubkey,
+    ) -> anyhow::Result<Vec<Instruction>> {
+        ensure!(
+            !account.transfer_hook,
+            "its mint has a transfer hook, move the balance with a wallet instead"
+        );
+        let ata = get_associated_token_address_with_program_id(
+            recipient,
+            &account.mint,
+            &account.program_id,
+        );
+        ensure!(
+            ata != account.address,
+            "it is the recipient's own token account"
+        );
+        let existing = self
+            .token_accounts
+            .iter()
+            .find(|(other, _)| other.address == ata);
+        ensure!(
+            !existing.is_some_and(|(ata, _)| ata.frozen),
+            "the recipient's token account {} is frozen",
+            ata
+        );
+
+        let mut ixs = vec![
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &self.fee_payer,
+                recipient,
+                &account.mint,
+                &account.program_id,
+            ),
+        ];
+        // MemoTransfer on the destination requires a memo right before the transfer.
+        // Accounts of other wallets are not loaded, so they always get one.
+        if recipient != &account.owner {
+            ixs.push(spl_memo::build_memo(b"Transfer token account balance", &[]));
+        } else if existing.is_some_and(|(ata, _)| ata.memo_required) {
+            ixs.push(spl_memo::build_memo(b"Consolidate token account", &[]));
+        }
+        let signers = self.signers_for(&account.owner);
+        ixs.push(spl_token_2022::instruction::transfer_checked(
+            &account.program_id,
+            &account.address,
+            &account.mint,
+            &ata,
+            &account.owner,
+            &signers.iter().collect::<Vec<_>>(),
+            amount,
+            account.decimals,
+        )?);
+        Ok(ixs)
+    }
+
+    /// Clear the transfer fees withheld in `account`. When we are the mint's withdraw
+    /// authority they are withdrawn into the account itself and burned with the rest,
+    /// returning the amount credited; otherwise they are harvested to the mint.
+    fn create_withheld_fees_instruction(
+        &self,
+        account: &TokenAccountInfo,
+        withdraw_authority: Option<Pubkey>,
+    ) -> anyhow::Result<(Instruction, u64)> {
+        use spl_token_2022::extension::transfer_fee::instruction;
+
+        let authority = self.authority;
+        if withdraw_authority == Some(authority) && account.owner == authority {
+            let signers = self.signers_for(&authority);
+            let ix = instruction::withdraw_withheld_tokens_from_accounts(
+                &account.program_id,
+                &account.mint,
+                &account.address,
+                &authority,
+                &signers.iter().collect::<Vec<_>>(),
+                &[&account.address],
+            )?;
+            Ok((ix, account.withheld_amount))
+        } else {
+            let ix = instruction::harvest_withheld_tokens_to_mint(
+                &account.program_id,
+                &account.mint,
+                &[&account.address],
+            )?;
+            Ok((ix, 0))
+        }
+    }
+
+    pub fn create_revoke_instruction(
+        &self,
+        account: &TokenAccountInfo,
+    ) -> anyhow::Result<Instruction> {
+        let signers = self.signers_for(&account.owner);
+        spl_token_2022::instruction::revoke(
+            &account.program_id,
+            &account.address,
+            &account.owner,
+            &signers.iter().collect::<Vec<_>>(),
+        )
+        .map_err(|e| e.into())
+    }
+
+    fn create_burn_instruction(
+        &self,
+        account: &TokenAccountInfo,
+        amount: u64,
+    ) -> anyhow::Result<Instruction> {
+        let signers = self.signers_for(&account.owner);
+        spl_token_2022::instruction::burn(
+            &account.program_id,
+            &account.address,
+            &account.mint,
+            &account.owner,
+            &signers.iter().collect::<Vec<_>>(),
+            amount,
+        )
+        .map_err(|e| e.into())
+    }
+
+    fn create_close_ata_instruction(
+        &self,
+        account: &TokenAccountInfo,
+    ) -> anyhow::Result<Instruction> {
+        let authority = account.close_authority.unwrap_or(account.owner);
+        let signers = self.signers_for(&authority);
+        close_account(
+            &account.program_id,
+            &account.address,
+            &self.rent_destination,
+            &authority,
+            &signers.iter().collect::<Vec<_>>(),
+        )
+        .map_err(|e| e.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch;
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+    /// An SPL Token account of `owner` holding `balance` of a fresh mint, at an address
+    /// other than its ATA
+    fn account(owner: Pubkey, balance: u64) -> TokenAccountInfo {
+        TokenAccountInfo {
+            address: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            program_id: spl_token::id(),
+            owner,
+            close_authority: None,
+            delegate: None,
+            delegated_amount: 0,
+            ui_delegated_amount: "0".to_string(),
+            frozen: false,
+            balance,
+            decimals: 6,
+            canonical: false,
+            transfer_hook: false,
+            withheld_amount: 0,
+            needs_emptying: false,
+            memo_required: false,
+            cpi_guard: false,
+            unsupported: Vec::new(),
+            ui_balance: balance.to_string(),
+            lamports: 2_039_280,
+            nft: None,
+            action: AccountAction::Default,
+        }
+    }
+
+    /// The canonical ATA of `owner` for the mint of `account`
+    fn ata_for(owner: Pubkey, account: &TokenAccountInfo) -> TokenAccountInfo {
+        TokenAccountInfo {
+            address: get_associated_token_address_with_program_id(
+                &owner,
+                &account.mint,
+                &account.program_id,
+            ),
+            canonical: true,
+            owner,
+            ..account.clone()
+        }
+    }
+
+    /// A planner in burn and close mode for `owner`, who pays fees and receives rent
+    fn planner(owner: Pubkey, token_accounts: &[(TokenAccountInfo, bool)]) -> Planner<'_> {
+        Planner {
+            owner,
+            authority: owner,
+            multisig: None,
+            fee_payer: owner,
+            rent_destination: owner,
+            transfer_destination: None,
+            burn_token: true,
+            close_ata: true,
+            consolidate: false,
+            token_accounts,
+        }
+    }
+
+    fn burn(account: &TokenAccountInfo, amount: u64) -> Instruction {
+        spl_token_2022::instruction::burn(
+            &account.program_id,
+            &account.address,
+            &account.mint,
+            &account.owner,
+            &[],
+            amount,
+        )
+        .unwrap()
+    }
+
+    fn close(account: &TokenAccountInfo, destination: &Pubkey, authority: &Pubkey) -> Instruction {
+        close_account(
+            &account.program_id,
+            &account.address,
+            destination,
+            authority,
+            &[],
+        )
+        .unwrap()
+    }
+
+    /// Create-ATA, optional memo and transfer of `account`'s balance to `recipient`
+    fn transfer(
+        account: &TokenAccountInfo,
+        recipient: &Pubkey,
+        memo: Option<&str>,
+    ) -> Vec<Instruction> {
+        let ata = get_associated_token_address_with_program_id(
+            recipient,
+            &account.mint,
+            &account.program_id,
+        );
+        let mut ixs = vec![create_associated_token_account_idempotent(
+            &account.owner,
+            recipient,
+            &account.mint,
+            &account.program_id,
+        )];
+        ixs.extend(memo.map(|memo| spl_memo::build_memo(memo.as_bytes(), &[])));
+        ixs.push(
+            spl_token_2022::instruction::transfer_checked(
+                &account.program_id,
+                &account.address,
+                &account.mint,
+                &ata,
+                &account.owner,
+                &[],
+                account.balance,
+                account.decimals,
+            )
+            .unwrap(),
+        );
+        ixs
+    }
+
+    fn plan(planner: &Planner, account: &TokenAccountInfo) -> anyhow::Result<Vec<Instruction>> {
+        planner.plan_account(account, Fetched::default())
+    }
+
+    #[test]
+    fn actions_follow_the_mode_and_row() {
+        let owner = Pubkey::new_unique();
+        let mut account = account(owner, 5);
+        let mut planner = planner(owner, &[]);
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [burn(&account, 5), close(&account, &owner, &owner)]
+        );
+
+        // Burn-only keeps the account, close-only never burns
+        planner.close_ata = false;
+        assert_eq!(plan(&planner, &account).unwrap(), [burn(&account, 5)]);
+        planner.burn_token = false;
+        planner.close_ata = true;
+        account.balance = 0;
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [close(&account, &owner, &owner)]
+        );
+
+        // The row's own action wins over the mode
+        account.balance = 5;
+        account.action = AccountAction::BurnAndClose;
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [burn(&account, 5), close(&account, &owner, &owner)]
+        );
+
+        account.action = AccountAction::RevokeOnly;
+        account.delegate = Some(Pubkey::new_unique());
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [spl_token_2022::instruction::revoke(
+                &account.program_id,
+                &account.address,
+                &owner,
+                &[]
+            )
+            .unwrap()]
+        );
+    }
+
+    #[test]
+    fn rent_goes_to_the_destination_and_close_authority() {
+        let owner = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let mut planner = planner(owner, &[]);
+        planner.rent_destination = treasury;
+
+        // Closing wrapped SOL returns the lamports instead of burning them
+        let mut native = account(owner, 1_000_000);
+        native.mint = spl_token::native_mint::id();
+        assert_eq!(
+            plan(&planner, &native).unwrap(),
+            [close(&native, &treasury, &owner)]
+        );
+
+        // Accounts of others we are the close authority of are closed by it and not burned
+        let mut other = account(Pubkey::new_unique(), 0);
+        other.close_authority = Some(owner);
+        assert_eq!(
+            plan(&planner, &other).unwrap(),
+            [close(&other, &treasury, &owner)]
+        );
+    }
+
+    #[test]
+    fn multisig_members_sign() {
+        let multisig = MultisigAuthority {
+            account: Pubkey::new_unique(),
+            threshold: 2,
+            members: 3,
+            signers: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let account = account(multisig.account, 5);
+        let planner = Planner {
+            authority: multisig.account,
+            multisig: Some(&multisig),
+            ..planner(multisig.signers[0], &[])
+        };
+        let signers = multisig.signers.iter().collect::<Vec<_>>();
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [
+                spl_token_2022::instruction::burn(
+                    &account.program_id,
+                    &account.address,
+                    &account.mint,
+                    &multisig.account,
+                    &signers,
+                    5,
+                )
+                .unwrap(),
+                close_account(
+                    &account.program_id,
+                    &account.address,
+                    &multisig.signers[0],
+                    &multisig.account,
+                    &signers,
+                )
+                .unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn nfts_burn_through_token_metadata() {
+        let owner = Pubkey::new_unique();
+        let mut account = account(owner, 1);
+        account.decimals = 0;
+        let nft = metaplex::Nft {
+            metadata: metaplex::metadata_address(&account.mint),
+            edition: Some(metaplex::edition_address(&account.mint)),
+            collection_metadata: None,
+            token_record: None,
+            lamports: 0,
+        };
+        let burn_nft = metaplex::burn_instruction(
+            &nft,
+            &owner,
+            &account.mint,
+            &account.address,
+            &account.program_id,
+        );
+        account.nft = Some(nft);
+        let planner = planner(owner, &[]);
+        assert_eq!(plan(&planner, &account).unwrap(), [burn_nft]);
+
+        account.cpi_guard = true;
+        assert!(plan(&planner, &account).is_err());
+    }
+
+    #[test]
+    fn consolidation_memos_only_when_required() {
+        let owner = Pubkey::new_unique();
+        let auxiliary = account(owner, 5);
+        let mut ata = ata_for(owner, &auxiliary);
+        let token_accounts = [(ata.clone(), false)];
+        let planner = Planner {
+            consolidate: true,
+            ..planner(owner, &token_accounts)
+        };
+        let consolidated = plan(&planner, &auxiliary).unwrap();
+        assert_eq!(consolidated[..2], transfer(&auxiliary, &owner, None));
+        assert_eq!(consolidated[2..], [close(&auxiliary, &owner, &owner)]);
+
+        // MemoTransfer on the canonical ATA
+        ata.memo_required = true;
+        let token_accounts = [(ata, false)];
+        let planner = Planner {
+            token_accounts: &token_accounts,
+            ..planner
+        };
+        let consolidated = plan(&planner, &auxiliary).unwrap();
+        assert_eq!(
+            consolidated[..3],
+            transfer(&auxiliary, &owner, Some("Consolidate token account"))
+        );
+    }
+
+    #[test]
+    fn transfers_to_other_wallets_always_carry_a_memo() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut account = account(owner, 5);
+        account.action = AccountAction::TransferAndClose;
+        let mut planner = planner(owner, &[]);
+        assert!(plan(&planner, &account).is_err());
+
+        planner.transfer_destination = Some(recipient);
+        let ixs = plan(&planner, &account).unwrap();
+        assert_eq!(
+            ixs[..3],
+            transfer(&account, &recipient, Some("Transfer token account balance"))
+        );
+        assert_eq!(ixs[3], close(&account, &owner, &owner));
+
+        // A separate fee payer funds the ATA
+        let fee_payer = Pubkey::new_unique();
+        planner.fee_payer = fee_payer;
+        let ixs = plan(&planner, &account).unwrap();
+        assert_eq!(
+            ixs[0],
+            create_associated_token_account_idempotent(
+                &fee_payer,
+                &recipient,
+                &account.mint,
+                &account.program_id,
+            )
+        );
+    }
+
+    #[test]
+    fn transfers_are_refused_into_the_account_itself_or_frozen_accounts() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let mut account = account(owner, 5);
+        account.action = AccountAction::TransferAndClose;
+
+        // Sending the canonical ATA's balance to its own owner
+        let canonical = ata_for(owner, &account);
+        let to_owner = Planner {
+            transfer_destination: Some(owner),
+            ..planner(owner, &[])
+        };
+        let error = plan(&to_owner, &canonical).unwrap_err();
+        assert!(error.to_string().contains("recipient's own token account"));
+
+        let mut frozen = ata_for(recipient, &account);
+        frozen.frozen = true;
+        let token_accounts = [(frozen, false)];
+        let to_frozen = Planner {
+            transfer_destination: Some(recipient),
+            ..planner(owner, &token_accounts)
+        };
+        let error = plan(&to_frozen, &account).unwrap_err();
+        assert!(error.to_string().contains("is frozen"));
+
+        account.transfer_hook = true;
+        let to_recipient = Planner {
+            transfer_destination: Some(recipient),
+            ..planner(owner, &[])
+        };
+        let error = plan(&to_recipient, &account).unwrap_err();
+        assert!(error.to_string().contains("transfer hook"));
+    }
+
+    #[test]
+    fn withheld_fees_are_withdrawn_or_harvested() {
+        use spl_token_2022::extension::transfer_fee::instruction::{
+            harvest_withheld_tokens_to_mint, withdraw_withheld_tokens_from_accounts,
+        };
+
+        let owner = Pubkey::new_unique();
+        let mut account = account(owner, 5);
+        account.program_id = spl_token_2022::id();
+        account.withheld_amount = 2;
+        let planner = planner(owner, &[]);
+
+        // As the withdraw authority, the fees are withdrawn into the account and burned
+        let fetched = Fetched {
+            withdraw_withheld_authority: Some(owner),
+            ..Fetched::default()
+        };
+        assert_eq!(
+            planner.plan_account(&account, fetched).unwrap(),
+            [
+                withdraw_withheld_tokens_from_accounts(
+                    &account.program_id,
+                    &account.mint,
+                    &account.address,
+                    &owner,
+                    &[],
+                    &[&account.address],
+                )
+                .unwrap(),
+                burn(&account, 7),
+                close(&account, &owner, &owner),
+            ]
+        );
+
+        let harvest = harvest_withheld_tokens_to_mint(
+            &account.program_id,
+            &account.mint,
+            &[&account.address],
+        )
+        .unwrap();
+        assert_eq!(
+            plan(&planner, &account).unwrap(),
+            [harvest, burn(&account, 5), close(&account, &owner, &owner)]
+        );
+    }
+
+    #[test]
+    fn confidential_balances_are_emptied_first() {
+        let owner = Pubkey::new_unique();
+        let mut account = account(owner, 0);
+        account.needs_emptying = true;
+        let emptying = vec![spl_memo::build_memo(b"empty", &[])];
+        let fetched = Fetched {
+            emptying: emptying.clone(),
+            ..Fetched::default()
+        };
+        let ixs = planner(owner, &[]).plan_account(&account, fetched).unwrap();
+        assert_eq!(ixs[0], emptying[0]);
+        assert_eq!(ixs.last(), Some(&close(&account, &owner, &owner)));
+    }
+
+    /// The refusal for `selected`, signable by `owner` alone
+    fn refusal(planner: &Planner, selected: &[TokenAccountInfo]) -> String {
+        planner.refusal(selected, &[planner.owner]).unwrap()
+    }
+
+    #[test]
+    fn blocked_accounts_are_refused() {
+        let owner = Pubkey::new_unique();
+        let planner = planner(owner, &[]);
+        let mut frozen = account(owner, 5);
+        frozen.frozen = true;
+        assert!(refusal(&planner, &[frozen]).contains("frozen"));
+
+        let mut foreign_authority = account(owner, 0);
+        foreign_authority.close_authority = Some(Pubkey::new_unique());
+        assert!(refusal(&planner, &[foreign_authority.clone()]).contains("close authority"));
+        // Only when it is closed
+        foreign_authority.action = AccountAction::RevokeOnly;
+        foreign_authority.delegate = Some(Pubkey::new_unique());
+        assert_eq!(planner.refusal(&[foreign_authority], &[owner]), None);
+    }
+
+    #[test]
+    fn balances_are_not_moved_into_accounts_being_closed() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let auxiliary = account(owner, 5);
+        let ata = ata_for(owner, &auxiliary);
+        let planner = Planner {
+            consolidate: true,
+            transfer_destination: Some(recipient),
+            ..planner(owner, &[])
+        };
+        assert!(refusal(&planner, &[auxiliary.clone(), ata]).contains("canonical ATA"));
+
+        // Listed ATAs of the transfer recipient, such as the signer's own when the
+        // multisig sends to it, cannot be closed in the same run
+        let mut transferred = auxiliary;
+        transferred.action = AccountAction::TransferAndClose;
+        let recipient_ata = TokenAccountInfo {
+            action: AccountAction::CloseOnly,
+            balance: 0,
+            ..ata_for(recipient, &transferred)
+        };
+        assert!(refusal(&planner, &[transferred, recipient_ata]).contains("being closed too"));
+    }
+
+    #[test]
+    fn actions_that_cannot_apply_are_refused() {
+        let owner = Pubkey::new_unique();
+        let mut planner = planner(owner, &[]);
+
+        let mut transferred = account(owner, 5);
+        transferred.action = AccountAction::TransferAndClose;
+        assert!(refusal(&planner, &[transferred]).contains("no transfer destination"));
+
+        let mut revoked = account(owner, 5);
+        revoked.action = AccountAction::RevokeOnly;
+        assert!(refusal(&planner, &[revoked]).contains("no delegate"));
+
+        let mut programmable = account(owner, 1);
+        programmable.nft = Some(metaplex::Nft {
+            metadata: Pubkey::new_unique(),
+            edition: None,
+            collection_metadata: None,
+            token_record: Some(Pubkey::new_unique()),
+            lamports: 0,
+        });
+        programmable.action = AccountAction::CloseOnly;
+        assert!(refusal(&planner, &[programmable]).contains("programmable NFTs"));
+
+        let mut close_only = account(owner, 5);
+        close_only.action = AccountAction::CloseOnly;
+        assert!(refusal(&planner, &[close_only]).contains("close-only only closes empty"));
+
+        planner.close_ata = false;
+        let mut native = account(owner, 5);
+        native.mint = spl_token::native_mint::id();
+        assert!(refusal(&planner, &[native]).contains("burn-only skips WSOL"));
+        planner.close_ata = true;
+
+        // We can close an account of another owner, but not burn its balance
+        let mut other = account(Pubkey::new_unique(), 5);
+        other.close_authority = Some(owner);
+        assert!(refusal(&planner, &[other]).contains("which must empty it"));
+    }
+
+    #[test]
+    fn transfer_rows_fill_transactions_by_size() {
+        let owner = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let planner = Planner {
+            transfer_destination: Some(recipient),
+            ..planner(owner, &[])
+        };
+        let groups: Vec<_> = (0..12)
+            .map(|_| {
+                let mut account = account(owner, 5);
+                account.action = AccountAction::TransferAndClose;
+                plan(&planner, &account).unwrap()
+            })
+            .collect();
+        let batches = batch::pack(groups.clone(), &owner, &[]).unwrap();
+        assert_eq!(batches.concat(), groups.concat());
+        // Four instructions and three new accounts each, so far fewer than twelve fit
+        assert!(batches.len() >= 3);
+        assert!(batches.iter().all(|batch| batch.len() % 4 == 0));
+    }
+}
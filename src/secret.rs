@@ -0,0 +1,106 @@
+use solana_sdk::signature::Keypair;
+use zeroize::Zeroizing;
+
+use std::ops::Deref;
+
+/// Length of a serialized Solana keypair (secret key followed by public key)
+pub const KEYPAIR_LEN: usize = 64;
+
+/// Fixed-size buffer for a decoded keypair, wiped when dropped.
+/// Decoding into a preallocated array avoids the reallocations that would leave
+/// unwiped copies behind in a growing `Vec`.
+pub type KeypairBytes = Zeroizing<[u8; KEYPAIR_LEN]>;
+
+/// Secret text (private keys, seed phrases, passwords), wiped when dropped
+pub type SecretString = Zeroizing<String>;
+
+/// An empty [`KeypairBytes`] buffer
+pub fn keypair_bytes() -> KeypairBytes {
+    Zeroizing::new([0u8; KEYPAIR_LEN])
+}
+
+/// A signing keypair kept at a fixed heap address for the whole session.
+///
+/// The page holding it is locked with mlock/VirtualLock where the OS allows, so the
+/// secret is never written to swap. The inner ed25519 secret key overwrites itself
+/// when dropped, after which the pages are unlocked.
+pub struct LockedKeypair {
+    // Declared first so the lock is released only after the keypair has been wiped
+    keypair: Box<Keypair>,
+    _lock: Option<region::LockGuard>,
+}
+
+impl LockedKeypair {
+    pub fn new(keypair: Keypair) -> Self {
+        let keypair = Box::new(keypair);
+        // Locking can fail under a low RLIMIT_MEMLOCK; keep going without it
+        let lock = region::lock(&*keypair as *const Keypair, size_of::<Keypair>()).ok();
+        Self {
+            keypair,
+            _lock: lock,
+        }
+    }
+
+    /// Whether the keypair's pages are currently locked in memory
+    pub fn is_locked(&self) -> bool {
+        self._lock.is_some()
+    }
+}
+
+impl Deref for LockedKeypair {
+    type Target = Keypair;
+
+    fn deref(&self) -> &Keypair {
+        &self.keypair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+    use zeroize::Zeroize;
+
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn keypair_bytes_are_wiped_on_drop() {
+        let mut slot = MaybeUninit::new(keypair_bytes());
+        // SAFETY: the slot was just initialized
+        unsafe { slot.assume_init_mut() }.fill(0xAB);
+        // SAFETY: dropped once; the array stays readable as plain bytes afterwards
+        let wiped = unsafe {
+            slot.assume_init_drop();
+            std::ptr::read(slot.as_ptr() as *const [u8; KEYPAIR_LEN])
+        };
+        assert_eq!(wiped, [0u8; KEYPAIR_LEN]);
+    }
+
+    #[test]
+    fn secret_string_wipes_its_whole_buffer() {
+        let mut secret = SecretString::new(String::with_capacity(64));
+        secret.push_str("4NMwxzmYj2uvHuq8xoqhY8RXg63KSVJM1DXkpbmkUY7YQWuoyQgFnnzn6yo3CMnqZasnNPNuAT2TLwQsCaKkUddp");
+        let (ptr, capacity) = (secret.as_ptr(), secret.capacity());
+
+        // The same wipe `Zeroizing` runs when dropped, done early so the buffer can be read
+        secret.zeroize();
+        assert!(secret.is_empty());
+        // SAFETY: the allocation is still owned by `secret`
+        let buffer = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(buffer.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn locked_keypair_keeps_its_address() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let locked = LockedKeypair::new(keypair);
+        let address = &*locked as *const Keypair;
+
+        // Moving the wrapper must not move the secret out of the locked page
+        let mut holder = vec![locked];
+        let moved = holder.pop().unwrap();
+        assert_eq!(&*moved as *const Keypair, address);
+        assert_eq!(moved.pubkey(), pubkey);
+    }
+}